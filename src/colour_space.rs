@@ -0,0 +1,181 @@
+//! Colour spaces that are defined in the resources
+//!
+//! The device colour spaces, `DeviceGray`, `DeviceRGB` and `DeviceCMYK`, can
+//! be used directly, but ICC based colour spaces and spot colours have to be
+//! described in the `ColorSpace` dictionary of the resources. They are added
+//! with [crate::document::Resources::set_colour_space] and used through the
+//! key they are added with, see [Colour]. See section 8.6 of the PDF spec.
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::document::Colour;
+
+/// A colour space that needs a definition in the resources.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ColourSpace {
+    /// Colours calibrated by an embedded ICC profile, see section 8.6.5.5 of
+    /// the PDF spec.
+    IccBased {
+        profile: ObjectId,
+        components: usize,
+    },
+    /// A single colourant, such as a spot colour ink, see section 8.6.6.4 of
+    /// the PDF spec.
+    Separation { name: String, alternate: Colour },
+}
+
+impl ColourSpace {
+    /// Embed an RGB or CMYK ICC profile.
+    ///
+    /// The number of components is read from the colour space in the profile
+    /// header.
+    pub fn icc(doc: &mut Document, profile: Vec<u8>) -> Self {
+        let (components, alternate) = match profile.get(16..20) {
+            Some(b"RGB ") => (3, "DeviceRGB"),
+            Some(b"CMYK") => (4, "DeviceCMYK"),
+            signature => panic!(
+                "Only RGB and CMYK ICC profiles are supported but the profile was {:?}",
+                signature.map(String::from_utf8_lossy)
+            ),
+        };
+        let dict = dictionary! {
+            "N" => components as i64,
+            "Alternate" => alternate,
+        };
+        let profile = doc.add_object(Stream::new(dict, profile).with_compression(true));
+        Self::IccBased {
+            profile,
+            components,
+        }
+    }
+
+    /// Embed an ICC profile read from a file, see [ColourSpace::icc].
+    pub fn icc_from_file<P: AsRef<std::path::Path>>(
+        doc: &mut Document,
+        path: P,
+    ) -> std::io::Result<Self> {
+        Ok(Self::icc(doc, std::fs::read(path)?))
+    }
+
+    /// A spot colour ink called `name`.
+    ///
+    /// Where the ink is not available, tints are shown as a blend from white
+    /// to `alternate`, which must be a device colour.
+    pub fn separation(name: &str, alternate: Colour) -> Self {
+        if alternate.device_space().is_none() {
+            panic!(
+                "Spot colour alternates must be device colours but was {:?}",
+                alternate
+            );
+        }
+        Self::Separation {
+            name: name.to_owned(),
+            alternate,
+        }
+    }
+
+    /// The number of components in a colour.
+    pub fn components(&self) -> usize {
+        match self {
+            Self::IccBased { components, .. } => *components,
+            Self::Separation { .. } => 1,
+        }
+    }
+
+    pub fn as_object(&self) -> Object {
+        match self {
+            Self::IccBased { profile, .. } => vec!["ICCBased".into(), (*profile).into()].into(),
+            Self::Separation { name, alternate } => vec![
+                "Separation".into(),
+                Object::Name(name.as_bytes().to_vec()),
+                alternate.device_space().unwrap().into(),
+                tint_transform(*alternate).into(),
+            ]
+            .into(),
+        }
+    }
+}
+
+/// A linear blend from no ink to the full alternate colour.
+fn tint_transform(alternate: Colour) -> Dictionary {
+    let full = alternate.components();
+    let none = match alternate {
+        Colour::Cmyk(..) => vec![0.; 4],
+        _ => vec![1.; full.len()],
+    };
+    let array = |c: Vec<f32>| c.into_iter().map(Object::Real).collect::<Vec<_>>();
+    dictionary! {
+        "FunctionType" => 2,
+        "Domain" => vec![0.into(), 1.into()],
+        "C0" => array(none),
+        "C1" => array(full),
+        "N" => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(signature: &[u8; 4]) -> Vec<u8> {
+        let mut header = vec![0; 128];
+        header[16..20].copy_from_slice(signature);
+        header
+    }
+
+    #[test]
+    fn icc_profiles_are_embedded() {
+        let mut doc = Document::with_version("1.7");
+        let space = ColourSpace::icc(&mut doc, profile(b"CMYK"));
+        assert_eq!(space.components(), 4);
+
+        let array = space.as_object();
+        let array = array.as_array().unwrap();
+        assert_eq!(array[0].as_name_str().unwrap(), "ICCBased");
+        let stream = doc
+            .get_object(array[1].as_reference().unwrap())
+            .unwrap()
+            .as_stream()
+            .unwrap();
+        assert_eq!(stream.dict.get(b"N").unwrap().as_i64().unwrap(), 4);
+        assert_eq!(
+            stream
+                .dict
+                .get(b"Alternate")
+                .unwrap()
+                .as_name_str()
+                .unwrap(),
+            "DeviceCMYK"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only RGB and CMYK")]
+    fn gray_icc_profiles_are_not_supported() {
+        ColourSpace::icc(&mut Document::with_version("1.7"), profile(b"GRAY"));
+    }
+
+    #[test]
+    fn spot_colours_blend_from_white() {
+        let space = ColourSpace::separation("Brand Red", Colour::Rgb(0.9, 0., 0.1));
+        let array = space.as_object();
+        let array = array.as_array().unwrap();
+        assert_eq!(array[1].as_name_str().unwrap(), "Brand Red");
+        assert_eq!(array[2].as_name_str().unwrap(), "DeviceRGB");
+        let function = array[3].as_dict().unwrap();
+        let c0: Vec<f32> = function
+            .get(b"C0")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|o| o.as_float().unwrap())
+            .collect();
+        assert_eq!(c0, vec![1., 1., 1.]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be device colours")]
+    fn spot_colour_alternates_are_device_colours() {
+        ColourSpace::separation("Spot", Colour::Separation("Other", 1.));
+    }
+}
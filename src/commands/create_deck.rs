@@ -1,29 +1,48 @@
 use lopdf::{content::Content, dictionary, xobject, Document, Object, ObjectId};
 
 use crate::{
+    colour_space::ColourSpace,
     commands::{create_maxi, create_mini},
     config::CreateConfig,
-    document::{Colour, ContentBuilder, DocumentAdditions, Resources},
+    document::{
+        Colour, ContentBuilder, DocumentAdditions, LineCap, LineJoin, LineStyle, Resources,
+    },
     fonts::{self, FontType0Builder},
+    form::FormBuilder,
+    graphics_state::{BlendMode, GraphicsState, SoftMask},
+    import::PageImporter,
+    layout::Paragraph,
+    pattern::{Pattern, TilingPattern},
+    shading::Shading,
+    shapes,
 };
 
-const DARK_BLUE: Colour = (0.106, 0.259, 0.471);
-const LIGHT_BLUE: Colour = (0., 0.624, 0.855);
-const BRIGHT_RED: Colour = (0.97, 0., 0.1);
-const BLACK: Colour = (0., 0., 0.);
-const GREY: Colour = (0.5, 0.5, 0.5);
+/// Right hand edge of the slide content, where the title underline ends.
+const RIGHT_MARGIN: i32 = 900;
 
-const MAGENTA: Colour = (0.9, 0.2, 0.6);
-const MUSTARD: Colour = (0.8, 0.8, 0.5);
-const PALE_BLUE: Colour = (0.6, 0.6, 0.9);
-const PALE_GREEN: Colour = (0.6, 0.9, 0.6);
-const PALE_RED: Colour = (0.9, 0.6, 0.6);
+const DARK_BLUE: Colour = Colour::Rgb(0.106, 0.259, 0.471);
+const LIGHT_BLUE: Colour = Colour::Rgb(0., 0.624, 0.855);
+const BRIGHT_RED: Colour = Colour::Rgb(0.97, 0., 0.1);
+/// Accent red as a spot colour, shown as [BRIGHT_RED] on screen.
+const SPOT_RED: Colour = Colour::Separation("Red", 1.);
+const BLACK: Colour = Colour::Gray(0.);
+const GREY: Colour = Colour::Gray(0.5);
+const WHITE: Colour = Colour::Gray(1.);
+
+const MAGENTA: Colour = Colour::Rgb(0.9, 0.2, 0.6);
+const MUSTARD: Colour = Colour::Rgb(0.8, 0.8, 0.5);
+const PALE_BLUE: Colour = Colour::Rgb(0.6, 0.6, 0.9);
+const PALE_GREEN: Colour = Colour::Rgb(0.6, 0.9, 0.6);
+const PALE_RED: Colour = Colour::Rgb(0.9, 0.6, 0.6);
 
 fn lighter(colour: Colour, factor: f32) -> Colour {
-    (
-        colour.0 + (1. - colour.0) * factor,
-        colour.1 + (1. - colour.1) * factor,
-        colour.2 + (1. - colour.2) * factor,
+    let Colour::Rgb(r, g, b) = colour else {
+        panic!("Can only lighten RGB colours but was {:?}", colour);
+    };
+    Colour::Rgb(
+        r + (1. - r) * factor,
+        g + (1. - g) * factor,
+        b + (1. - b) * factor,
     )
 }
 
@@ -117,8 +136,21 @@ pub fn main(config: CreateConfig) {
 
     // TODO @robyoung add resources and lopdf::Document to a wrapper type
     let mut resources = Resources::default();
-    setup_fonts(&mut doc, &mut resources);
+    setup_fonts(&mut doc, &mut resources, &config);
     setup_images(&mut doc, &mut resources);
+    setup_documents(&mut doc, &mut resources, &config);
+    let web_doc = Document::load("pdfs/web.pdf").expect("could not read web.pdf");
+    let mut web_pages = PageImporter::new(&web_doc);
+    let form_id = web_pages.form(&mut doc, 1).add_to_doc(&mut doc);
+    resources.set_xobject("Web1", form_id);
+    resources.set_colour_space("Red", ColourSpace::separation("Bright Red", BRIGHT_RED));
+    resources.add_graphics_state(highlight_state());
+    let horse_fade = closer::fade(&mut doc);
+    resources.add_graphics_state(horse_fade.clone());
+    resources.add_shading(title_bar());
+    resources.add_pattern(&mut doc, title_background());
+    resources.add_pattern(&mut doc, hatching());
+    resources.add_pattern(&mut doc, dots());
 
     let resources_id = resources.add_to_doc(&mut doc);
     let mut page_ids = vec![
@@ -126,11 +158,14 @@ pub fn main(config: CreateConfig) {
         what::page(&mut doc, &resources, pages_id),
         history::page(&mut doc, &resources, pages_id),
         three_documents::page(&mut doc, &resources, pages_id),
+        // the real web page, sharing its fonts and images with the form above
+        doc.import_page(&mut web_pages, 1, pages_id),
         tools::page(&mut doc, &resources, pages_id),
     ];
+    web_pages.finish(&mut doc);
     page_ids.append(&mut file_structure::pages(&mut doc, &resources, pages_id));
     page_ids.append(&mut doc_structure::pages(&mut doc, &resources, pages_id));
-    page_ids.push(closer::page(&mut doc, &resources, pages_id));
+    page_ids.push(closer::page(&mut doc, &resources, pages_id, &horse_fade));
 
     let pages = dictionary! {
         "Type" => "Pages",
@@ -141,14 +176,19 @@ pub fn main(config: CreateConfig) {
     };
     doc.objects.insert(pages_id, Object::Dictionary(pages));
     doc.add_catalog(pages_id);
+    for warning in resources.finish(&mut doc) {
+        eprintln!("Warning: {}", warning);
+    }
     config.apply_and_save(&mut doc);
 
     println!("create deck");
 }
 
-fn setup_fonts(doc: &mut Document, resources: &mut Resources) {
+fn setup_fonts(doc: &mut Document, resources: &mut Resources, config: &CreateConfig) {
     let font_ref = FontType0Builder::from_file("assets/Georgia.ttf")
         .expect("could not read font file")
+        .subset(config.subset)
+        .shaping(true)
         .add_to_doc(doc);
     resources.set_font("F1", font_ref);
 
@@ -159,16 +199,95 @@ fn setup_fonts(doc: &mut Document, resources: &mut Resources) {
     // manually import font from maxi
     let font_ref = FontType0Builder::from_file("assets/FiraCodeNerdFontMono-Medium.ttf")
         .expect("could not read font file")
+        .subset(config.subset)
+        .shaping(true)
         .add_to_doc(doc);
     resources.set_font("F3", font_ref);
+
+    // fall back to Fira Code for symbols, like arrows, that Georgia lacks
+    resources.set_font_stack("Body", &["F1", "F3"]);
+
+    // bullet squares are glyphs of a Type3 font that sets its own colour
+    let font_ref = fonts::type3()
+        .name("Icons")
+        .units_per_em(10)
+        .bbox(0, 0, 10, 10)
+        .coloured(true)
+        .glyph('■', 10., |b| {
+            b.colour(LIGHT_BLUE)
+                .begin_path(0, 0)
+                .append_straight_line(0, 10)
+                .append_straight_line(10, 10)
+                .append_straight_line(10, 0)
+                .close_subpath()
+                .fill_path()
+        })
+        .add_to_doc(doc);
+    resources.set_font("F4", font_ref);
 }
 
-fn setup_images(doc: &mut Document, resources: &mut Resources) {
-    let image_stream =
-        xobject::image("assets/web-small.jpg").expect("could not read web screenshot");
-    let image_id = doc.add_object(image_stream);
-    resources.set_xobject("Im1", image_id);
+/// Gradient from dark to light blue under slide titles.
+fn title_bar() -> Shading {
+    Shading::axial(
+        (50., 0.),
+        (RIGHT_MARGIN as f32, 0.),
+        &[
+            (0., DARK_BLUE),
+            (0.6, LIGHT_BLUE),
+            (1., lighter(LIGHT_BLUE, 0.8)),
+        ],
+    )
+}
+
+/// Pale blue glow behind the title page text, fading to white.
+fn title_background() -> Pattern {
+    Pattern::Shading(
+        Shading::radial(
+            (650., 300., 0.),
+            (650., 300., 450.),
+            &[
+                (0., lighter(LIGHT_BLUE, 0.85)),
+                (1., Colour::Rgb(1., 1., 1.)),
+            ],
+        )
+        .extend(false, true),
+    )
+}
+
+/// Diagonal stripes, painted in the colour of what is hatched.
+fn hatching() -> Pattern {
+    Pattern::Tiling(TilingPattern::uncoloured(6., 6., |b| {
+        // the corners continue the stripes from the neighbouring cells
+        b.line_width(2)
+            .draw_line((0, 0), (6, 6))
+            .draw_line((-3, 3), (3, 9))
+            .draw_line((3, -3), (9, 3))
+            .stroke_path()
+    }))
+}
+
+/// Light blue dots, with gaps between them as the cells are stepped further
+/// apart than their size.
+fn dots() -> Pattern {
+    Pattern::Tiling(
+        TilingPattern::coloured(3., 3., |b| {
+            b.colour(LIGHT_BLUE)
+                .append_ellipse(1.5, 1.5, 1., 1.)
+                .fill_path()
+        })
+        .step(5., 5.),
+    )
+}
 
+/// Translucent fill and outline for highlighting parts of a diagram.
+fn highlight_state() -> GraphicsState {
+    GraphicsState::new()
+        .fill_alpha(0.4)
+        .stroke_alpha(0.8)
+        .blend_mode(BlendMode::Multiply)
+}
+
+fn setup_images(doc: &mut Document, resources: &mut Resources) {
     let image_stream = xobject::image("assets/tnt-logo.png").expect("could not read tnt logo");
     let image_id = doc.add_object(image_stream);
     resources.set_xobject("Im3", image_id);
@@ -179,12 +298,45 @@ fn setup_images(doc: &mut Document, resources: &mut Resources) {
     resources.set_xobject("Im4", image_id);
 }
 
+/// Number of pages in maxi.pdf, each embedded as `Maxi1`, `Maxi2` and so on.
+const MAXI_PAGES: usize = 3;
+
+/// Embed the pages of mini.pdf and maxi.pdf as forms, with their own fonts
+/// and images.
+fn setup_documents(doc: &mut Document, resources: &mut Resources, config: &CreateConfig) {
+    let mini_doc = create_mini::generate_document();
+    let page_id = mini_doc.get_pages()[&1];
+    let form_id = FormBuilder::from_page(doc, &mini_doc, page_id).add_to_doc(doc);
+    resources.set_xobject("Mini1", form_id);
+
+    let maxi_doc = create_maxi::generate_document(&CreateConfig {
+        subset: config.subset,
+        ..CreateConfig::default()
+    });
+    assert_eq!(maxi_doc.get_pages().len(), MAXI_PAGES);
+    // one importer so that the pages share a single copy of the font and image
+    let mut maxi_pages = PageImporter::new(&maxi_doc);
+    for number in 1..=MAXI_PAGES {
+        let form_id = maxi_pages.form(doc, number as u32).add_to_doc(doc);
+        resources.set_xobject(&format!("Maxi{}", number), form_id);
+    }
+    maxi_pages.finish(doc);
+}
+
 mod title {
     //! Page 1 of the deck
     use super::*;
 
+    const DATE: &str = "January 2024";
+
     pub fn page(doc: &mut Document, resources: &Resources, pages_id: ObjectId) -> ObjectId {
         let content_builder = ContentBuilder::new(resources)
+            // fill the background
+            .save_graphics_state()
+            .fill_pattern(&title_background())
+            .append_rectangle(0, 0, 960, 540)
+            .fill_path()
+            .restore_graphics_state()
             // write text
             .begin_text()
             .font("F1", 38)
@@ -193,10 +345,13 @@ mod title {
             .text("What even is a PDF?")
             .end_text()
             .begin_text()
-            .font("F1", 17)
-            .text_position(770, 100)
+            .font("F1", 17);
+        // right align the date with the edge of the title
+        let date_width = content_builder.text_width(DATE);
+        let content_builder = content_builder
+            .text_position(RIGHT_MARGIN - date_width.round() as i32, 100)
             .colour(LIGHT_BLUE) // pale blue
-            .text("January 2024")
+            .text(DATE)
             .end_text();
 
         let content_builder = add_tnt_logo(content_builder);
@@ -220,7 +375,7 @@ mod title {
             .restore_graphics_state()
             // place white line
             .save_graphics_state()
-            .scolour((1., 1., 1.))
+            .scolour(WHITE)
             .line_width(line_width)
             .begin_path(x + line_offset, y)
             .append_straight_line(x + line_offset, y + line_height)
@@ -280,7 +435,7 @@ mod history {
         let item_vert_offset = 370;
         let item_vert_space = 50;
         let mut list_text = TextConfig::new(140, item_vert_offset);
-        let mut date_text = TextConfig::new(70, item_vert_offset).with_colour(BRIGHT_RED);
+        let mut date_text = TextConfig::new(70, item_vert_offset).with_colour(SPOT_RED);
         let content_builder = ContentBuilder::new(resources)
             .title("History of PDF")
             .text_with("1990", date_text.then_down(item_vert_space))
@@ -330,99 +485,62 @@ mod three_documents {
 
     pub fn page(doc: &mut Document, resources: &Resources, pages_id: ObjectId) -> ObjectId {
         let mut builder = ContentBuilder::new(resources);
-        builder = add_mini_doc(builder);
-        builder = add_maxi_doc(builder);
-        builder = add_web_doc(builder);
+        builder = add_mini_doc(doc, builder);
+        builder = add_maxi_doc(doc, builder);
+        builder = add_web_doc(doc, builder);
 
         builder.add_to_doc_with_page(doc, pages_id)
     }
 
-    fn add_mini_doc(mut b: ContentBuilder) -> ContentBuilder {
-        let mini_doc = create_mini::generate_document();
-        let media_box = get_media_box(&mini_doc);
-        let page_id = mini_doc.page_iter().next().unwrap();
-        let content = Content::decode(&mini_doc.get_page_content(page_id).unwrap()).unwrap();
-
-        b = b
-            .title("Three documents")
-            .text_at(50, 350, "mini.pdf")
-            .save_graphics_state()
-            .cm_position(50, 160)
-            .cm_scale(0.2, 0.2)
-            .scolour(BLACK)
-            .line_width(1.)
-            .begin_path(0, 0)
-            .append_straight_line(0, media_box[3])
-            .append_straight_line(media_box[2], media_box[3])
-            .append_straight_line(media_box[2], 0)
-            .close_subpath()
-            .stroke_path();
-
-        b.operations.extend(content.operations);
-        b.restore_graphics_state()
+    fn add_mini_doc<'a>(doc: &Document, b: ContentBuilder<'a>) -> ContentBuilder<'a> {
+        let b = b.title("Three documents").text_at(50, 350, "mini.pdf");
+        add_page(doc, b, "Mini1", (50, 160))
     }
 
-    fn as_f32(o: &Object) -> f32 {
-        o.as_f32()
-            .or_else(|_| o.as_i64().map(|i| i as f32))
-            .unwrap()
+    fn add_maxi_doc<'a>(doc: &Document, mut b: ContentBuilder<'a>) -> ContentBuilder<'a> {
+        b = b.text_at(350, 350, "maxi.pdf");
+        for i in 0..MAXI_PAGES {
+            let key = format!("Maxi{}", i + 1);
+            b = add_page(doc, b, &key, (200 + (i as i32 * 150), 190));
+        }
+        b
     }
 
-    fn get_media_box(doc: &Document) -> [f32; 4] {
-        let page_tree_id = doc
-            .catalog()
-            .and_then(|c| c.get(b"Pages"))
-            .and_then(Object::as_reference)
-            .unwrap();
-        let media_box = doc
-            .get_dictionary(page_tree_id)
-            .and_then(|d| d.get(b"MediaBox"))
+    /// Paint an embedded page at a fifth of its size with a frame around it.
+    fn add_page<'a>(
+        doc: &Document,
+        b: ContentBuilder<'a>,
+        key: &str,
+        position: (i32, i32),
+    ) -> ContentBuilder<'a> {
+        let form_id = b
+            .resources
+            .xobject(key)
+            .unwrap_or_else(|| panic!("no embedded page {}", key));
+        let bbox = doc
+            .get_object(form_id)
+            .and_then(Object::as_stream)
+            .and_then(|form| form.dict.get(b"BBox"))
             .and_then(Object::as_array)
             .unwrap();
-
-        [
-            as_f32(&media_box[0]),
-            as_f32(&media_box[1]),
-            as_f32(&media_box[2]),
-            as_f32(&media_box[3]),
-        ]
-    }
-
-    fn add_maxi_doc(mut b: ContentBuilder) -> ContentBuilder {
-        let maxi_doc = create_maxi::generate_document(&CreateConfig::default());
-        let media_box = get_media_box(&maxi_doc);
-        for (i, page_id) in maxi_doc.page_iter().enumerate() {
-            let content = Content::decode(&maxi_doc.get_page_content(page_id).unwrap()).unwrap();
-            b = b
-                .text_at(350, 350, "maxi.pdf")
-                .save_graphics_state()
-                .cm_position(200 + (i as i32 * 150), 190)
-                .cm_scale(0.2, 0.2)
-                .scolour(BLACK)
-                .line_width(1.)
-                .begin_path(0, 0)
-                .append_straight_line(0, media_box[3])
-                .append_straight_line(media_box[2], media_box[3])
-                .append_straight_line(media_box[2], 0)
-                .close_subpath()
-                .stroke_path();
-
-            b.operations.extend(content.operations);
-            b = b.restore_graphics_state();
-        }
-
-        b
+        let (width, height) = (
+            bbox[2].as_float().unwrap() - bbox[0].as_float().unwrap(),
+            bbox[3].as_float().unwrap() - bbox[1].as_float().unwrap(),
+        );
+        let b = b
+            .save_graphics_state()
+            .cm_position(position.0, position.1)
+            .cm_scale(0.2, 0.2);
+        shapes::rectangle(0., 0., width, height)
+            .stroke(BLACK, 1.)
+            .draw(b)
+            .add_xobject(key)
+            .restore_graphics_state()
     }
 
-    fn add_web_doc(mut b: ContentBuilder) -> ContentBuilder {
-        b = b
-            .text_at(690, 350, "web.pdf")
-            .save_graphics_state()
-            .cm_position(670, 50)
-            .cm_scale(200, 270)
-            .add_xobject("Im1")
-            .restore_graphics_state();
-        b
+    fn add_web_doc<'a>(doc: &Document, b: ContentBuilder<'a>) -> ContentBuilder<'a> {
+        let b = b.text_at(690, 350, "web.pdf");
+        add_page(doc, b, "Web1", (670, 160))
     }
 }
 
@@ -432,7 +550,7 @@ mod tools {
     use super::*;
     pub fn page(doc: &mut Document, resources: &Resources, pages_id: ObjectId) -> ObjectId {
         let mut black = TextConfig::new(160, 360);
-        let mut red = TextConfig::new(70, 360).with_colour(BRIGHT_RED);
+        let mut red = TextConfig::new(70, 360).with_colour(SPOT_RED);
         let bullet_space = 60;
         let bullet_x = -20;
         let bullet_y = 3;
@@ -492,7 +610,7 @@ mod file_structure {
             .file_overview()
             .add_section(MAGENTA, 10) // Header
             .add_section(MUSTARD, 457 - 10) // Body
-            .add_section(PALE_BLUE, 606 - 457) // xref
+            .add_hatched_section(PALE_BLUE, 606 - 457) // xref
             .add_section(PALE_GREEN, 637 - 606) // Trailer
             .add_section(PALE_RED, 656 - 637) // Startxref
             .build(b);
@@ -520,7 +638,7 @@ mod file_structure {
             .file_overview()
             .add_section(MAGENTA, 10) // Header
             .add_section(MUSTARD, 104371 - 10) // Body
-            .add_section(PALE_BLUE, 104701 - 104371) // xref
+            .add_hatched_section(PALE_BLUE, 104701 - 104371) // xref
             .add_section(PALE_GREEN, 104734 - 104701) // Trailer
             .add_section(PALE_RED, 104755 - 104734) // Startxref
             .build(b);
@@ -558,12 +676,12 @@ mod file_structure {
             // first section
             .add_section(MAGENTA, 10) // Header
             .add_section(MUSTARD, 447) // Body
-            .add_section(PALE_BLUE, 149) // xref
+            .add_hatched_section(PALE_BLUE, 149) // xref
             .add_section(PALE_GREEN, 31) // Trailer
             .add_section(PALE_RED, 19) // Startxref
             // second section
             .add_section(MUSTARD, 100) // Body
-            .add_section(PALE_BLUE, 161) // xref
+            .add_hatched_section(PALE_BLUE, 161) // xref
             .add_section(PALE_GREEN, 31) // Trailer
             .add_section(PALE_RED, 19) // Startxref
             .build(b);
@@ -741,7 +859,7 @@ mod doc_structure {
             .file_overview()
             .add_section(MAGENTA, 10) // Header
             .add_section(MUSTARD, 350) // Body
-            .add_section(PALE_BLUE, 100) // xref
+            .add_hatched_section(PALE_BLUE, 100) // xref
             .add_section(PALE_GREEN, 50) // Trailer
             .add_section(PALE_RED, 50) // Startxref
             .build(b);
@@ -776,14 +894,9 @@ mod doc_structure {
         let b = round_box.origin((offset_x - 240, 210)).build(b);
         let b = b.text_with("Page Tree", c.at((offset_x - 230, 230)));
 
-        let highlight = lighter(PALE_GREEN, 0.2);
         let page_x = offset_x - 360;
         let page_y = 130;
-        let b = round_box
-            .origin((offset_x + 50, page_y))
-            .clone()
-            .fill(highlight)
-            .build(b);
+        let b = round_box.origin((offset_x + 50, page_y)).build(b);
         let b = b.text_with("Resources", c.at((offset_x + 60, page_y + 20)));
 
         let b = round_box.origin((page_x, page_y)).build(b);
@@ -792,11 +905,7 @@ mod doc_structure {
         let b = round_box.origin((page_x - 55, 60)).build(b);
         let b = b.text_with("Content", c.at((page_x - 38, 80)));
 
-        let b = round_box
-            .origin((page_x + 55, 60))
-            .clone()
-            .fill(highlight)
-            .build(b);
+        let b = round_box.origin((page_x + 55, 60)).build(b);
         let b = b.text_with("Resources", c.at((page_x + 65, 80)));
 
         let page_x = offset_x - 120;
@@ -806,11 +915,7 @@ mod doc_structure {
         let b = round_box.origin((page_x - 55, 60)).build(b);
         let b = b.text_with("Content", c.at((page_x - 38, 80)));
 
-        let b = round_box
-            .origin((page_x + 55, 60))
-            .clone()
-            .fill(highlight)
-            .build(b);
+        let b = round_box.origin((page_x + 55, 60)).build(b);
         let b = b.text_with("Resources", c.at((page_x + 65, 80)));
 
         // Connecting lines
@@ -832,6 +937,25 @@ mod doc_structure {
             .stroke_path()
             .restore_graphics_state();
 
+        // translucent highlights over the resources boxes
+        let highlights = [
+            (offset_x + 50, page_y),
+            (offset_x - 360 + 55, 60),
+            (offset_x - 120 + 55, 60),
+        ];
+        let b = highlights
+            .iter()
+            .fold(
+                b.save_graphics_state().graphics_state(&highlight_state()),
+                |b, &(x, y)| {
+                    shapes::rounded_rectangle(x as f32 - 5., y as f32 - 5., 110., 60., 8.)
+                        .stroke(PALE_GREEN, 2.)
+                        .fill(PALE_GREEN)
+                        .draw(b)
+                },
+            )
+            .restore_graphics_state();
+
         b.add_to_doc_with_page(doc, pages_id)
     }
 
@@ -899,24 +1023,30 @@ mod doc_structure {
         let b = round_box.origin((page_x + 55, 60)).build(b);
         let b = b.text_with("Resources", c.at((page_x + 65, 80)));
 
-        // Connecting lines
-        let b = b
-            .save_graphics_state()
-            // Trailer -> Info Dict
-            .draw_line((offset_x + 30, 350), (offset_x + 130, 330))
-            // Trailer -> Catalog
-            .draw_line((offset_x + 30, 350), (offset_x - 30, 330))
-            // Catalog -> Page Tree
-            .draw_line((offset_x - 70, 280), (offset_x - 190, 260))
-            .draw_line((offset_x - 190, 210), (offset_x + 100, page_y + 50))
-            .draw_line((offset_x - 190, 210), (offset_x - 310, page_y + 50))
-            .draw_line((offset_x - 190, 210), (offset_x - 70, page_y + 50))
-            .draw_line((offset_x - 310, 130), (offset_x - 365, 110))
-            .draw_line((offset_x - 310, 130), (offset_x - 255, 110))
-            .draw_line((offset_x - 70, 130), (offset_x - 125, 110))
-            .draw_line((offset_x - 70, 130), (offset_x - 15, 110))
-            .stroke_path()
-            .restore_graphics_state();
+        // Connecting arrows, dashed as they are references rather than boxes
+        let connector = LineStyle::new(1.)
+            .cap(LineCap::Round)
+            .join(LineJoin::Round)
+            .dash(&[4., 3.], 0.);
+        let b = draw_arrows(
+            b.save_graphics_state().line_style(&connector),
+            &[
+                // Trailer -> Info Dict
+                ((offset_x + 30, 350), (offset_x + 130, 330)),
+                // Trailer -> Catalog
+                ((offset_x + 30, 350), (offset_x - 30, 330)),
+                // Catalog -> Page Tree
+                ((offset_x - 70, 280), (offset_x - 190, 260)),
+                ((offset_x - 190, 210), (offset_x + 100, page_y + 50)),
+                ((offset_x - 190, 210), (offset_x - 310, page_y + 50)),
+                ((offset_x - 190, 210), (offset_x - 70, page_y + 50)),
+                ((offset_x - 310, 130), (offset_x - 365, 110)),
+                ((offset_x - 310, 130), (offset_x - 255, 110)),
+                ((offset_x - 70, 130), (offset_x - 125, 110)),
+                ((offset_x - 70, 130), (offset_x - 15, 110)),
+            ],
+        )
+        .restore_graphics_state();
 
         let b = b
             .save_graphics_state()
@@ -959,42 +1089,133 @@ mod doc_structure {
 
         let b = b
             .save_graphics_state()
-            // From root
-            .draw_line((ox / 2, 0), (-ox / 2, -50))
-            .draw_line((ox / 2, 0), ((ox / 2) * 3, -50))
+            .line_style(&connector)
             // Left side
             .draw_line((-ox, -75), (-ox - 20, -75))
             .draw_line((-ox - 20, -75), (-ox - 20, gy(4)))
-            .draw_line((-ox - 20, gy(2)), (-ox, gy(2)))
-            .draw_line((-ox - 20, gy(3)), (-ox, gy(3)))
-            .draw_line((-ox - 20, gy(4)), (-ox, gy(4)))
             // Right side
             .draw_line((ox + 100, -75), (ox + 100 + 20, -75))
             .draw_line((ox + 100 + 20, -75), (ox + 100 + 20, gy(4)))
-            .draw_line((ox + 100, gy(2)), (ox + 100 + 20, gy(2)))
-            .draw_line((ox + 100, gy(3)), (ox + 100 + 20, gy(3)))
-            .draw_line((ox + 100, gy(4)), (ox + 100 + 20, gy(4)))
-            .stroke_path()
-            .restore_graphics_state();
+            .stroke_path();
+        let b = draw_arrows(
+            b,
+            &[
+                // From root
+                ((ox / 2, 0), (-ox / 2, -50)),
+                ((ox / 2, 0), ((ox / 2) * 3, -50)),
+                // Left side
+                ((-ox - 20, gy(2)), (-ox, gy(2))),
+                ((-ox - 20, gy(3)), (-ox, gy(3))),
+                ((-ox - 20, gy(4)), (-ox, gy(4))),
+                // Right side
+                ((ox + 100 + 20, gy(2)), (ox + 100, gy(2))),
+                ((ox + 100 + 20, gy(3)), (ox + 100, gy(3))),
+                ((ox + 100 + 20, gy(4)), (ox + 100, gy(4))),
+            ],
+        )
+        .restore_graphics_state();
 
         let b = b.restore_graphics_state();
 
         b.add_to_doc_with_page(doc, pages_id)
     }
+
+    /// Draw arrows from each parent to its child in the current stroke style.
+    fn draw_arrows<'a>(b: ContentBuilder<'a>, arrows: &[(Coord, Coord)]) -> ContentBuilder<'a> {
+        arrows.iter().fold(b, |b, &((x1, y1), (x2, y2))| {
+            shapes::arrow((x1 as f32, y1 as f32), (x2 as f32, y2 as f32), 6.).draw(b)
+        })
+    }
 }
 
 mod closer {
 
     use super::*;
 
-    pub fn page(doc: &mut Document, resources: &Resources, pages_id: ObjectId) -> ObjectId {
+    const FRAME: (f32, f32, f32, f32) = (720., 280., 180., 130.);
+    const LENS: (f32, f32, f32) = (855., 320., 30.);
+
+    /// A soft mask that fades the horse out towards the edges of the frame.
+    pub fn fade(doc: &mut Document) -> GraphicsState {
+        // white, and so opaque, in the middle and black at the corners
+        let vignette = Shading::radial(
+            (0.5, 0.5, 0.),
+            (0.5, 0.5, 0.7),
+            &[(0., WHITE), (0.6, WHITE), (1., BLACK)],
+        );
+        let mut resources = Resources::default();
+        resources.add_shading(vignette.clone());
+        let (x, y, width, height) = FRAME;
+        let mask_id = FormBuilder::new(doc, [0., 0., 1., 1.], &resources, |b| b.shading(&vignette))
+            .matrix([width, 0., 0., height, x, y])
+            .transparency_group("DeviceGray")
+            .add_to_doc(doc);
+        GraphicsState::new().soft_mask(SoftMask::Luminosity(mask_id))
+    }
+
+    pub fn page(
+        doc: &mut Document,
+        resources: &Resources,
+        pages_id: ObjectId,
+        fade: &GraphicsState,
+    ) -> ObjectId {
         let c = TextConfig::new(170, 200).with_font("F1", 80);
+        let (x, y, width, height) = FRAME;
+        let (lens_x, lens_y, lens_radius) = LENS;
 
-        ContentBuilder::new(resources)
+        let b = ContentBuilder::new(resources)
             .title("Let's take a look")
+            // the horse from maxi.pdf, cropped to a rounded frame with a hole
+            // left for the lens
+            .save_graphics_state()
+            .append_rounded_rectangle(x, y, width, height, 15.)
+            .append_ellipse(lens_x, lens_y, lens_radius, lens_radius)
+            .clip_path_even_odd()
+            .graphics_state(fade);
+        let b = add_horse(b).restore_graphics_state();
+
+        // the horse magnified twice under the lens, kept within the frame
+        let b = b
+            .save_graphics_state()
+            .clip_rounded_rectangle(x, y, width, height, 15.)
+            .clip_ellipse(lens_x, lens_y, lens_radius, lens_radius)
+            .cm_position(lens_x, lens_y)
+            .cm_scale(2, 2)
+            .cm_position(-lens_x, -lens_y);
+        let b = add_horse(b).restore_graphics_state();
+
+        b
+            // a dotted mount around the frame, the inner edge leaves the picture
+            // showing
+            .save_graphics_state()
+            .fill_pattern(&dots())
+            .scolour(GREY)
+            .line_width(0.5)
+            .append_rounded_rectangle(x - 8., y - 8., width + 16., height + 16., 20.)
+            .append_rounded_rectangle(x, y, width, height, 15.)
+            .fill_stroke_path_even_odd()
+            // the rim and handle of the lens
+            .colour(DARK_BLUE)
+            .append_ellipse(lens_x, lens_y, lens_radius + 4., lens_radius + 4.)
+            .append_ellipse(lens_x, lens_y, lens_radius, lens_radius)
+            .fill_path_even_odd()
+            .scolour(DARK_BLUE)
+            .line_style(&LineStyle::new(6.).cap(LineCap::Round))
+            .draw_line(
+                (lens_x + lens_radius + 2., lens_y - lens_radius - 2.),
+                (lens_x + lens_radius + 20., lens_y - lens_radius - 20.),
+            )
+            .stroke_path()
+            .restore_graphics_state()
             .text_with("Into the files...", c)
             .add_to_doc_with_page(doc, pages_id)
     }
+
+    fn add_horse(b: ContentBuilder) -> ContentBuilder {
+        b.cm_position(697, 280)
+            .cm_scale(226, 130)
+            .add_xobject("Im4")
+    }
 }
 type Coord = (i32, i32);
 
@@ -1005,27 +1226,31 @@ trait ContentBuilderAdditions {
     fn title(self, text: &str) -> Self;
     fn text_at(self, x: i32, y: i32, text: &str) -> Self;
     fn text_with(self, text: &str, config: TextConfig) -> Self;
-    fn thick_blue_line(self, from: Coord, to: Coord) -> Self;
     fn thin_blue_line(self, from: Coord, to: Coord) -> Self;
 }
 
 impl<'a> ContentBuilderAdditions for ContentBuilder<'a> {
     fn bullet(self, x: i32, y: i32) -> Self {
-        let size = 6;
         self.save_graphics_state()
-            .colour(LIGHT_BLUE)
-            .begin_path(x, y)
-            .append_straight_line(x, y + size)
-            .append_straight_line(x + size, y + size)
-            .append_straight_line(x + size, y)
-            .append_straight_line(x, y)
-            .fill_path()
+            .begin_text()
+            .font("F4", 6)
+            .text_position(x, y)
+            .text("■")
+            .end_text()
             .restore_graphics_state()
     }
 
     fn bullet_text(self, text: &str, config: TextConfig) -> Self {
+        let x = config.x + 15;
+        let paragraph = Paragraph::new(text, "F1", 20)
+            .position(x as f32, config.y as f32 + 15.)
+            .width((RIGHT_MARGIN - x) as f32)
+            .leading(24.);
         self.bullet(config.x, config.y)
-            .text_at(config.x + 15, config.y - 3, text)
+            .save_graphics_state()
+            .colour(DARK_BLUE)
+            .paragraph(&paragraph)
+            .restore_graphics_state()
     }
 
     fn title_text(self, text: &str) -> Self {
@@ -1038,11 +1263,12 @@ impl<'a> ContentBuilderAdditions for ContentBuilder<'a> {
     }
 
     fn title(self, text: &str) -> Self {
-        self.title_text(text).thick_blue_line((50, 440), (900, 440))
-    }
-
-    fn thick_blue_line(self, from: Coord, to: Coord) -> Self {
-        blue_line(self, from, to, 1.)
+        self.title_text(text)
+            // underline with a gradient bar
+            .save_graphics_state()
+            .clip_rectangle(50, 439, RIGHT_MARGIN - 50, 2)
+            .shading(&title_bar())
+            .restore_graphics_state()
     }
 
     fn thin_blue_line(self, from: Coord, to: Coord) -> Self {
@@ -1054,7 +1280,7 @@ impl<'a> ContentBuilderAdditions for ContentBuilder<'a> {
     }
 
     fn text_with(self, text: &str, config: TextConfig) -> Self {
-        let (font_name, font_size) = config.font.unwrap_or((String::from("F1"), 20));
+        let (font_name, font_size) = config.font.unwrap_or((String::from("Body"), 20));
         self.begin_text()
             .font(&font_name, font_size)
             .text_position(config.x, config.y)
@@ -1115,7 +1341,6 @@ struct RoundBox {
     width: f32,
     height: f32,
     radius: f32,
-    line_width: f32,
     stroke_colour: Colour,
     fill_colour: Option<Colour>,
 }
@@ -1127,7 +1352,6 @@ impl RoundBox {
             width,
             height,
             radius: 15.,
-            line_width: 1.,
             stroke_colour: BLACK,
             fill_colour: None,
         }
@@ -1143,11 +1367,6 @@ impl RoundBox {
         self
     }
 
-    fn line_width(mut self, line_width: f32) -> Self {
-        self.line_width = line_width;
-        self
-    }
-
     fn colour(mut self, colour: Colour) -> Self {
         self.stroke_colour = colour;
         self
@@ -1158,11 +1377,6 @@ impl RoundBox {
         self
     }
 
-    fn no_fill(&mut self) -> &mut Self {
-        self.fill_colour = None;
-        self
-    }
-
     fn build<'b>(&self, b: ContentBuilder<'b>) -> ContentBuilder<'b> {
         let b = self.setup_state(b);
         let b = self.draw_box(b);
@@ -1170,71 +1384,18 @@ impl RoundBox {
     }
 
     fn setup_state<'b>(&self, b: ContentBuilder<'b>) -> ContentBuilder<'b> {
-        let b = b
-            .save_graphics_state()
+        b.save_graphics_state()
             .cm_position(self.origin.0, self.origin.1)
-            .scolour(self.stroke_colour)
-            .line_width(self.line_width);
-        if let Some(colour) = self.fill_colour {
-            b.colour(colour)
-        } else {
-            b
-        }
     }
 
     fn draw_box<'b>(&self, b: ContentBuilder<'b>) -> ContentBuilder<'b> {
-        let k: f32 = 4.0 / 3.0 * (f32::sqrt(2.0) - 1.0);
-        let radius = self.radius;
-        let width = self.width;
-        let height = self.height;
-        let b = b
-            .begin_path(radius, 0)
-            .append_straight_line(width - radius, 0)
-            .append_curve(
-                // (width - r, 0) to (width, r)
-                (width - radius) + radius * k,
-                0,
-                width,
-                radius * k,
-                width,
-                0. + radius,
-            )
-            .append_straight_line(width, height - radius)
-            .append_curve(
-                // (width, height - r) to (width - r, height)
-                width,
-                (height - radius) + radius * k,
-                (width - radius) + radius * k,
-                height,
-                width - radius,
-                height,
-            )
-            .append_straight_line(radius, height)
-            .append_curve(
-                // (r, height) to (0, height - r)
-                radius * k,
-                height,
-                0,
-                (height - radius) + radius * k,
-                0,
-                height - radius,
-            )
-            .append_straight_line(0, radius)
-            .append_curve(
-                // (0, r) to (r, 0)
-                0,
-                radius * k,
-                radius * k,
-                0,
-                radius,
-                0,
-            );
-
-        if self.fill_colour.is_some() {
-            b.fill_stroke_path()
-        } else {
-            b.stroke_path()
+        let shape = shapes::rounded_rectangle(0., 0., self.width, self.height, self.radius)
+            .stroke(self.stroke_colour, 1.);
+        match self.fill_colour {
+            Some(colour) => shape.fill(colour),
+            None => shape,
         }
+        .draw(b)
     }
 
     fn file_overview(self) -> FileOverview {
@@ -1246,6 +1407,17 @@ impl RoundBox {
 struct Section {
     colour: Colour,
     size: usize,
+    hatched: bool,
+}
+
+impl Section {
+    fn stroke_colour<'b>(&self, b: ContentBuilder<'b>) -> ContentBuilder<'b> {
+        if self.hatched {
+            b.stroke_uncoloured_pattern(&hatching(), self.colour)
+        } else {
+            b.scolour(self.colour)
+        }
+    }
 }
 
 struct FileOverview {
@@ -1264,7 +1436,21 @@ impl FileOverview {
     }
 
     fn add_section(mut self, colour: Colour, size: usize) -> Self {
-        self.sections.push(Section { colour, size });
+        self.sections.push(Section {
+            colour,
+            size,
+            hatched: false,
+        });
+        self
+    }
+
+    /// Add a section drawn with diagonal stripes to set it apart.
+    fn add_hatched_section(mut self, colour: Colour, size: usize) -> Self {
+        self.sections.push(Section {
+            colour,
+            size,
+            hatched: true,
+        });
         self
     }
 
@@ -1294,9 +1480,8 @@ impl FileOverview {
             if used_width > 0. {
                 let end = x_margin + line_length * f32::min(used_width + lines, 1.);
 
-                b = b
-                    .save_graphics_state()
-                    .scolour(section.colour)
+                b = section
+                    .stroke_colour(b.save_graphics_state())
                     .line_width(stroke_width)
                     .begin_path(x_margin + line_length * used_width, y)
                     .append_straight_line(end, y)
@@ -1316,9 +1501,8 @@ impl FileOverview {
 
             // draw full lines
             for _ in 0..lines.floor() as usize {
-                b = b
-                    .save_graphics_state()
-                    .scolour(section.colour)
+                b = section
+                    .stroke_colour(b.save_graphics_state())
                     .line_width(stroke_width)
                     .begin_path(x_margin, y)
                     .append_straight_line(x_margin + line_length, y)
@@ -1331,9 +1515,8 @@ impl FileOverview {
             // draw a partial line for any remainder
             used_width = lines.fract();
             if used_width > 0. && lines_done < self.num_lines {
-                b = b
-                    .save_graphics_state()
-                    .scolour(section.colour)
+                b = section
+                    .stroke_colour(b.save_graphics_state())
                     .line_width(stroke_width)
                     .begin_path(x_margin, y)
                     .append_straight_line(x_margin + line_length * used_width, y)
@@ -1,18 +1,19 @@
+use std::f32::consts::PI;
+
 use lopdf::{
     content::{Content, Operation},
     dictionary, xobject, Document, Object, ObjectId, Stream,
 };
 
 use crate::{
-    config::{CreateConfig, FontFile, FontType},
-    document::DocumentAdditions,
+    config::{CreateConfig, FontType},
+    document::{Colour, ContentBuilder, DocumentAdditions, Resources},
     fonts::{self, FontReference},
+    layout::Paragraph,
+    shapes,
 };
 
-static FIRA_CODE: FontFile = FontFile {
-    full: "assets/FiraCodeNerdFontMono-Medium.ttf",
-    subset: "assets/FiraCodeNerdFontMono-Medium.subset.ttf",
-};
+static FIRA_CODE: &str = "assets/FiraCodeNerdFontMono-Medium.ttf";
 // static ROBOTO_FONT_PATH: &str = "assets/RobotoMedium.ttf";
 
 pub fn main(config: CreateConfig) {
@@ -23,13 +24,17 @@ pub fn main(config: CreateConfig) {
 pub(crate) fn generate_document(config: &CreateConfig) -> Document {
     let mut doc = Document::with_version("1.7");
 
-    let font_data = std::fs::read(config.font_path(&FIRA_CODE)).expect("could not read font file");
+    let font_data = std::fs::read(FIRA_CODE).expect("could not read font file");
 
     if let FontType::Type0 = config.font_type {
-        let font_ref = fonts::type0(&font_data).add_to_doc(&mut doc);
+        let font_ref = fonts::type0(&font_data)
+            .subset(config.subset)
+            .add_to_doc(&mut doc);
         generate_pages(config, doc, &font_ref)
     } else {
-        let font_ref = fonts::true_type(&font_data).add_to_doc(&mut doc);
+        let font_ref = fonts::true_type(&font_data)
+            .subset(config.subset)
+            .add_to_doc(&mut doc);
         generate_pages(config, doc, &font_ref)
     }
 }
@@ -63,6 +68,9 @@ fn generate_pages(
     };
     doc.objects.insert(pages_id, Object::Dictionary(pages));
     doc.add_catalog(pages_id);
+    for warning in font_ref.finish(&mut doc) {
+        eprintln!("Warning: {}", warning);
+    }
     doc
 }
 
@@ -72,17 +80,16 @@ fn create_page_one(
     pages_id: ObjectId,
     font_ref: &dyn FontReference,
 ) -> ObjectId {
+    let paragraph = Paragraph::new(
+        "This is a block of text that should spread across the page.",
+        "F3",
+        28,
+    )
+    .position(50., 300. + font_ref.ascent(28.))
+    .width(520.)
+    .leading(36.);
     let content = Content {
-        operations: vec![
-            Operation::new("BT", vec![]),
-            Operation::new("Tf", vec!["F3".into(), 28.into()]),
-            Operation::new("Td", vec![50.into(), 300.into()]),
-            Operation::new("TL", vec![36.into()]),
-            Operation::new("Tj", font_ref.render_text("This is a block of text that")),
-            Operation::new("T*", vec![]),
-            Operation::new("Tj", font_ref.render_text("should spread across the page.")),
-            Operation::new("ET", vec![]),
-        ],
+        operations: paragraph.layout(font_ref).operations(font_ref),
     };
     let content_id = doc.add_object(
         Stream::new(dictionary! {}, content.encode().unwrap())
@@ -128,87 +135,40 @@ fn create_page_two(
     })
 }
 
-fn make_circle_go_yay(radius: f64, center: (f64, f64)) -> Vec<Operation> {
-    // see: https://spencermortensen.com/articles/bezier-circle/
-    let c = 4.0 / 3.0 * (f64::sqrt(2.0) - 1.0);
+fn make_circle_go_yay(radius: f32, center: (f32, f32)) -> Vec<Operation> {
+    let resources = Resources::default();
+    let green = Colour::Rgb(0., 1., 0.);
     let yay_offset = radius / 2.0;
 
-    vec![
-        Operation::new("q", vec![]),
-        Operation::new("w", vec![5.into()]),
-        Operation::new("RG", vec![0.into(), 1.into(), 0.into()]),
-        Operation::new("m", vec![center.0.into(), (center.1 - radius).into()]),
-        // Make the circle
-        Operation::new(
-            "c",
-            vec![
-                (center.0 + radius * c).into(),
-                (center.1 - radius).into(),
-                (center.0 + radius).into(),
-                (center.1 - radius * c).into(),
-                (center.0 + radius).into(),
-                center.1.into(),
-            ],
-        ),
-        Operation::new(
-            "c",
-            vec![
-                (center.0 + radius).into(),
-                (center.1 + radius * c).into(),
-                (center.0 + radius * c).into(),
-                (center.1 + radius).into(),
-                center.0.into(),
-                (center.1 + radius).into(),
-            ],
-        ),
-        Operation::new(
-            "c",
-            vec![
-                (center.0 - radius * c).into(),
-                (center.1 + radius).into(),
-                (center.0 - radius).into(),
-                (center.1 + radius * c).into(),
-                (center.0 - radius).into(),
-                center.1.into(),
-            ],
-        ),
-        Operation::new(
-            "c",
-            vec![
-                (center.0 - radius).into(),
-                (center.1 - radius * c).into(),
-                (center.0 - radius * c).into(),
-                (center.1 - radius).into(),
-                center.0.into(),
-                (center.1 - radius).into(),
-            ],
-        ),
-        // Make the YAY
-        Operation::new(
-            "m",
-            vec![(center.0 - radius - yay_offset).into(), center.1.into()],
-        ),
-        Operation::new(
-            "l",
-            vec![
-                (center.0 - radius - yay_offset * 2.0).into(),
-                (center.1 + radius).into(),
-            ],
-        ),
-        Operation::new(
-            "m",
-            vec![(center.0 + radius + yay_offset).into(), center.1.into()],
-        ),
-        Operation::new(
-            "l",
-            vec![
-                (center.0 + radius + yay_offset * 2.0).into(),
-                (center.1 + radius).into(),
-            ],
-        ),
-        Operation::new("S", vec![]),
-        Operation::new("Q", vec![]),
-    ]
+    let b = shapes::circle(center.0, center.1, radius)
+        .stroke(green, 5.)
+        .draw(ContentBuilder::new(&resources));
+    // Make the face
+    let eye_x = radius * 0.35;
+    let eye_y = radius * 0.3;
+    let b = [-eye_x, eye_x].iter().fold(b, |b, x| {
+        shapes::polygon(center.0 + x, center.1 + eye_y, radius * 0.12, 3)
+            .fill(green)
+            .draw(b)
+    });
+    let b = shapes::arc(center.0, center.1, radius * 0.6, PI * 7. / 6., PI * 2. / 3.)
+        .stroke(green, 5.)
+        .draw(b);
+    // Make the YAY
+    b.save_graphics_state()
+        .scolour(green)
+        .line_width(5)
+        .draw_line(
+            (center.0 - radius - yay_offset, center.1),
+            (center.0 - radius - yay_offset * 2.0, center.1 + radius),
+        )
+        .draw_line(
+            (center.0 + radius + yay_offset, center.1),
+            (center.0 + radius + yay_offset * 2.0, center.1 + radius),
+        )
+        .stroke_path()
+        .restore_graphics_state()
+        .build_operations()
 }
 
 fn create_page_three(
@@ -10,11 +10,6 @@ pub enum FontType {
     Ttf,
 }
 
-pub struct FontFile {
-    pub full: &'static str,
-    pub subset: &'static str,
-}
-
 #[derive(Debug)]
 pub struct CreateConfig {
     pub xref_type: XrefType,
@@ -22,15 +17,15 @@ pub struct CreateConfig {
     pub compress: bool,
     pub compress_content: bool,
     pub subset: bool,
+    /// Merge identical fonts and images before saving.
+    pub dedupe: bool,
     pub output: PathBuf,
 }
 
 impl CreateConfig {
-    pub(crate) fn font_path(&self, font_file: &FontFile) -> &str {
-        if self.subset {
-            font_file.subset
-        } else {
-            font_file.full
+    pub(crate) fn dedupe(&self, doc: &mut lopdf::Document) {
+        if self.dedupe {
+            crate::dedupe::dedupe(doc);
         }
     }
 
@@ -49,6 +44,7 @@ impl CreateConfig {
     }
 
     pub(crate) fn apply_and_save(&self, doc: &mut lopdf::Document) {
+        self.dedupe(doc);
         self.compress(doc);
         self.apply_xref_table(doc);
         self.save(doc);
@@ -63,6 +59,7 @@ impl Default for CreateConfig {
             compress: false,
             compress_content: false,
             subset: false,
+            dedupe: false,
             output: PathBuf::from("output.pdf"),
         }
     }
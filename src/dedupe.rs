@@ -0,0 +1,224 @@
+//! Merge identical objects in a document
+//!
+//! Documents that are built from several sources often embed the same font
+//! program or image more than once. This pass finds streams, fonts and font
+//! descriptors with identical contents, keeps the first of each and rewrites
+//! every reference to the others. Merging objects can make the objects that
+//! refer to them identical too, so the pass repeats until nothing changes.
+use std::collections::{BTreeMap, HashMap};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Dictionary types that are safe to share between several parents.
+///
+/// Pages and other objects in a tree are never merged as they are identified
+/// by where they are, not just by what they contain.
+const SHARED_TYPES: [&str; 3] = ["Font", "FontDescriptor", "XObject"];
+
+/// Merge identical objects, returning the number of objects removed.
+pub fn dedupe(doc: &mut Document) -> usize {
+    let mut removed = 0;
+    loop {
+        let replacements = find_duplicates(doc);
+        if replacements.is_empty() {
+            return removed;
+        }
+        for id in replacements.keys() {
+            doc.objects.remove(id);
+        }
+        for object in doc.objects.values_mut() {
+            replace_references(object, &replacements);
+        }
+        replace_references_in_dictionary(&mut doc.trailer, &replacements);
+        removed += replacements.len();
+    }
+}
+
+/// Map each duplicate object to the first object with the same contents.
+fn find_duplicates(doc: &Document) -> BTreeMap<ObjectId, ObjectId> {
+    let mut seen: HashMap<Vec<u8>, ObjectId> = HashMap::new();
+    let mut replacements = BTreeMap::new();
+    for (&id, object) in doc.objects.iter() {
+        if !is_shareable(object) {
+            continue;
+        }
+        let mut key = Vec::new();
+        write_key(object, &mut key);
+        match seen.get(&key) {
+            Some(&original) => {
+                replacements.insert(id, original);
+            }
+            None => {
+                seen.insert(key, id);
+            }
+        }
+    }
+    replacements
+}
+
+fn is_shareable(object: &Object) -> bool {
+    match object {
+        Object::Stream(_) => true,
+        Object::Dictionary(dict) => dict
+            .get(b"Type")
+            .and_then(Object::as_name_str)
+            .is_ok_and(|t| SHARED_TYPES.contains(&t)),
+        _ => false,
+    }
+}
+
+/// Write an unambiguous representation of an object to compare contents by.
+///
+/// Dictionary keys are sorted so that the order they were added in does not
+/// matter, and the `Length` of streams is left out as it is only set when the
+/// document is saved.
+fn write_key(object: &Object, key: &mut Vec<u8>) {
+    match object {
+        Object::Null => key.push(b'n'),
+        Object::Boolean(b) => key.extend([b'b', *b as u8]),
+        Object::Integer(i) => {
+            key.push(b'i');
+            key.extend(i.to_be_bytes());
+        }
+        Object::Real(r) => {
+            key.push(b'r');
+            key.extend(r.to_be_bytes());
+        }
+        Object::Name(name) => write_bytes(b'/', name, key),
+        Object::String(s, format) => write_bytes(
+            match format {
+                lopdf::StringFormat::Literal => b'(',
+                lopdf::StringFormat::Hexadecimal => b'<',
+            },
+            s,
+            key,
+        ),
+        Object::Array(array) => {
+            key.push(b'[');
+            key.extend((array.len() as u64).to_be_bytes());
+            for item in array {
+                write_key(item, key);
+            }
+        }
+        Object::Dictionary(dict) => write_dictionary_key(dict, &[], key),
+        Object::Stream(stream) => {
+            key.push(b's');
+            write_dictionary_key(&stream.dict, &[b"Length"], key);
+            write_bytes(b'c', &stream.content, key);
+        }
+        Object::Reference((number, generation)) => {
+            key.push(b'R');
+            key.extend(number.to_be_bytes());
+            key.extend(generation.to_be_bytes());
+        }
+    }
+}
+
+fn write_dictionary_key(dict: &Dictionary, skip: &[&[u8]], key: &mut Vec<u8>) {
+    let mut entries: Vec<(&Vec<u8>, &Object)> = dict
+        .iter()
+        .filter(|(name, _)| !skip.contains(&name.as_slice()))
+        .collect();
+    entries.sort_by_key(|&(name, _)| name);
+    key.push(b'd');
+    key.extend((entries.len() as u64).to_be_bytes());
+    for (name, value) in entries {
+        write_bytes(b'/', name, key);
+        write_key(value, key);
+    }
+}
+
+/// Write bytes prefixed with a tag and their length.
+fn write_bytes(tag: u8, bytes: &[u8], key: &mut Vec<u8>) {
+    key.push(tag);
+    key.extend((bytes.len() as u64).to_be_bytes());
+    key.extend(bytes);
+}
+
+fn replace_references(object: &mut Object, replacements: &BTreeMap<ObjectId, ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if let Some(&original) = replacements.get(id) {
+                *id = original;
+            }
+        }
+        Object::Array(array) => {
+            for item in array.iter_mut() {
+                replace_references(item, replacements);
+            }
+        }
+        Object::Dictionary(dict) => replace_references_in_dictionary(dict, replacements),
+        Object::Stream(stream) => replace_references_in_dictionary(&mut stream.dict, replacements),
+        _ => {}
+    }
+}
+
+fn replace_references_in_dictionary(
+    dict: &mut Dictionary,
+    replacements: &BTreeMap<ObjectId, ObjectId>,
+) {
+    for (_, value) in dict.iter_mut() {
+        replace_references(value, replacements);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::{self, FontReference};
+    use lopdf::{dictionary, xobject};
+
+    #[test]
+    fn merges_identical_fonts_and_images() {
+        let mut doc = Document::with_version("1.5");
+        let first =
+            fonts::type0(&std::fs::read("assets/Georgia.ttf").unwrap()).add_to_doc(&mut doc);
+        let second =
+            fonts::type0(&std::fs::read("assets/Georgia.ttf").unwrap()).add_to_doc(&mut doc);
+        for font in [&first, &second] {
+            font.render_text("PDF");
+            font.finish(&mut doc);
+        }
+        let image = || xobject::image("assets/horsey.jpg").unwrap();
+        let first_image = doc.add_object(image());
+        let second_image = doc.add_object(image());
+        let resources = doc.add_object(dictionary! {
+            "Font" => dictionary! {
+                "F1" => first.object_id(),
+                "F2" => second.object_id(),
+            },
+            "XObject" => dictionary! {
+                "Im1" => first_image,
+                "Im2" => second_image,
+            },
+        });
+        let objects = doc.objects.len();
+
+        // the font file, descriptor, descendant font, ToUnicode and font
+        // objects are merged along with the image
+        assert_eq!(dedupe(&mut doc), 6);
+        assert_eq!(doc.objects.len(), objects - 6);
+        let resources = doc.get_dictionary(resources).unwrap();
+        let fonts = resources.get(b"Font").unwrap().as_dict().unwrap();
+        assert_eq!(
+            fonts.get(b"F2").unwrap().as_reference().unwrap(),
+            first.object_id()
+        );
+        let images = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        assert_eq!(
+            images.get(b"Im2").unwrap().as_reference().unwrap(),
+            first_image
+        );
+    }
+
+    #[test]
+    fn keeps_pages_with_the_same_contents() {
+        let mut doc = Document::with_version("1.5");
+        let page = || dictionary! { "Type" => "Page", "Contents" => Object::Null };
+        doc.add_object(page());
+        doc.add_object(page());
+
+        assert_eq!(dedupe(&mut doc), 0);
+        assert_eq!(doc.objects.len(), 2);
+    }
+}
@@ -9,11 +9,23 @@ use lopdf::{
 };
 use std::collections::HashMap;
 
-use crate::fonts::FontReference;
+use crate::colour_space::ColourSpace;
+use crate::fonts::{EncodingError, FontReference};
+use crate::graphics_state::GraphicsState;
+use crate::import::PageImporter;
+use crate::layout::Paragraph;
+use crate::pattern::Pattern;
+use crate::shading::Shading;
 
 /// Adds helper methods to [lopdf::Document].
 pub(crate) trait DocumentAdditions {
     fn add_catalog(&mut self, pages_id: ObjectId) -> ObjectId;
+    fn import_page(
+        &mut self,
+        pages: &mut PageImporter,
+        number: u32,
+        pages_id: ObjectId,
+    ) -> ObjectId;
 }
 
 impl DocumentAdditions for Document {
@@ -26,6 +38,20 @@ impl DocumentAdditions for Document {
         self.trailer.set("Root", catalog_id);
         catalog_id
     }
+
+    /// Import a page of another document, counting from 1, as a child of
+    /// `pages_id`
+    ///
+    /// Pages imported with the same importer share the objects they use, see
+    /// [PageImporter::import_page].
+    fn import_page(
+        &mut self,
+        pages: &mut PageImporter,
+        number: u32,
+        pages_id: ObjectId,
+    ) -> ObjectId {
+        pages.import_page(self, number, pages_id)
+    }
 }
 
 /// Possible values in a `TJ` text showing array.
@@ -69,6 +95,93 @@ impl<'a> From<i32> for TextItem<'a> {
     }
 }
 
+/// The shape of the ends of open subpaths and dashes.
+///
+/// See section 8.4.3.3 of the PDF spec.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum LineCap {
+    #[default]
+    Butt = 0,
+    Round = 1,
+    /// Like butt caps but extended by half the line width.
+    #[allow(dead_code)]
+    Square = 2,
+}
+
+/// The shape of the corners where path segments meet.
+///
+/// See section 8.4.3.4 of the PDF spec.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) enum LineJoin {
+    #[default]
+    Miter = 0,
+    Round = 1,
+    #[allow(dead_code)]
+    Bevel = 2,
+}
+
+/// How lines are stroked, defaulting to the initial graphics state.
+///
+/// See section 8.4.3 of the PDF spec.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct LineStyle {
+    width: f32,
+    cap: LineCap,
+    join: LineJoin,
+    miter_limit: f32,
+    dash: Vec<f32>,
+    dash_phase: f32,
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self {
+            width: 1.,
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: 10.,
+            dash: vec![],
+            dash_phase: 0.,
+        }
+    }
+}
+
+impl LineStyle {
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    pub fn cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Limit how far mitered corners stick out, as a ratio of the line width.
+    ///
+    /// Sharper corners are bevelled instead.
+    #[allow(dead_code)]
+    pub fn miter_limit(mut self, limit: f32) -> Self {
+        self.miter_limit = limit;
+        self
+    }
+
+    /// Dash lines with alternating lengths of dashes and gaps, starting
+    /// `phase` into the pattern. See [ContentBuilder::dash_pattern].
+    pub fn dash(mut self, lengths: &[f32], phase: f32) -> Self {
+        self.dash = lengths.to_vec();
+        self.dash_phase = phase;
+        self
+    }
+}
+
 /// Helper for building PDF content streams
 ///
 /// A fluid interface to avoid lots of verbose lopdf code.
@@ -77,16 +190,66 @@ impl<'a> From<i32> for TextItem<'a> {
 pub(crate) struct ContentBuilder<'a> {
     pub operations: Vec<Operation>,
     pub resources: &'a Resources,
-    current_font: Option<String>,
+    current_font: Option<(String, u32)>,
+    /// The font and size last selected with `Tf`, the font may be from a
+    /// font stack.
+    active_font: Option<(String, u32)>,
 }
 
-pub type Colour = (f32, f32, f32);
+/// A colour in a device colour space or a colour space in the resources.
+///
+/// Components range from 0 to 1. ICC based and spot colours name a colour
+/// space added with [Resources::set_colour_space]. See section 8.6 of the PDF
+/// spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Colour {
+    Gray(f32),
+    Rgb(f32, f32, f32),
+    Cmyk(f32, f32, f32, f32),
+    /// An RGB colour in an ICC based colour space.
+    IccRgb(&'static str, f32, f32, f32),
+    /// A CMYK colour in an ICC based colour space.
+    IccCmyk(&'static str, f32, f32, f32, f32),
+    /// A tint of a spot colour, from 0 for no ink to 1 for full ink.
+    Separation(&'static str, f32),
+}
+
+impl Colour {
+    pub(crate) fn components(&self) -> Vec<f32> {
+        match *self {
+            Self::Gray(g) => vec![g],
+            Self::Rgb(r, g, b) | Self::IccRgb(_, r, g, b) => vec![r, g, b],
+            Self::Cmyk(c, m, y, k) | Self::IccCmyk(_, c, m, y, k) => vec![c, m, y, k],
+            Self::Separation(_, tint) => vec![tint],
+        }
+    }
+
+    /// The device colour space of the colour, if it is in one.
+    pub(crate) fn device_space(&self) -> Option<&'static str> {
+        match self {
+            Self::Gray(_) => Some("DeviceGray"),
+            Self::Rgb(..) => Some("DeviceRGB"),
+            Self::Cmyk(..) => Some("DeviceCMYK"),
+            _ => None,
+        }
+    }
+
+    /// The key of the colour space in the resources, if it is not a device
+    /// colour.
+    fn resource_key(&self) -> Option<&'static str> {
+        match *self {
+            Self::IccRgb(key, ..) | Self::IccCmyk(key, ..) | Self::Separation(key, _) => Some(key),
+            _ => None,
+        }
+    }
+}
 
 impl<'a> ContentBuilder<'a> {
     pub fn new(resources: &'a Resources) -> Self {
         Self {
             operations: vec![],
             current_font: None,
+            active_font: None,
             resources,
         }
     }
@@ -114,25 +277,96 @@ impl<'a> ContentBuilder<'a> {
 
     /// Set the text font (`Tf`)
     ///
+    /// The font can be a font stack, in which case the first font in the stack
+    /// is selected and [ContentBuilder::text] switches between the fonts.
     /// See section 9.3.1 of the PDF spec
     pub fn font(mut self, font: &str, size: u32) -> Self {
         if !self.resources.fonts.contains_key(font) {
             panic!("Font {} does not exist in font map", font);
         }
-        self.current_font = Some(font.to_owned());
-        self.push("Tf", vec![font.into(), size.into()])
+        self.current_font = Some((font.to_owned(), size));
+        let first = self.resources.fonts.stack(font)[0].to_owned();
+        self.select_font(&first)
     }
 
-    #[allow(clippy::borrowed_box)]
-    fn current_font_ref(&self) -> &Box<dyn FontReference> {
+    /// Select a font from the font map with `Tf` at the current size, if it is
+    /// not already selected at that size
+    fn select_font(mut self, key: &str) -> Self {
+        let size = self.current_font.as_ref().map_or(0, |&(_, size)| size);
+        if let Some((active, active_size)) = &self.active_font {
+            if active == key && *active_size == size {
+                return self;
+            }
+        }
+        self.active_font = Some((key.to_owned(), size));
+        self.push("Tf", vec![key.into(), size.into()])
+    }
+
+    fn current_font_key(&self) -> &str {
         self.current_font
             .as_ref()
-            .and_then(|font| self.resources.fonts.get(font))
+            .map(|(font, _)| font.as_str())
             .expect(
                 "Attempting to use a font before referencing it. Call `font` to set a Tf first.",
             )
     }
 
+    /// The font reference for the current font, or the first font of a stack
+    #[allow(clippy::borrowed_box)]
+    fn current_font_ref(&self) -> &Box<dyn FontReference> {
+        let key = self.resources.fonts.stack(self.current_font_key())[0];
+        self.resources.fonts.get(key).unwrap()
+    }
+
+    fn current_font_size(&self) -> f32 {
+        self.current_font
+            .as_ref()
+            .map_or(0., |&(_, size)| size as f32)
+    }
+
+    /// Measure the width of text in the current font and size
+    pub fn text_width(&self, text: &str) -> f32 {
+        let fonts = &self.resources.fonts;
+        fonts
+            .runs(self.current_font_key(), text)
+            .into_iter()
+            .map(|(key, run)| {
+                fonts
+                    .get(key)
+                    .unwrap()
+                    .text_width(run, self.current_font_size())
+            })
+            .sum()
+    }
+
+    /// Measure the height of text written vertically in the current font and size
+    #[allow(dead_code)]
+    pub fn text_height(&self, text: &str) -> f32 {
+        let fonts = &self.resources.fonts;
+        fonts
+            .runs(self.current_font_key(), text)
+            .into_iter()
+            .map(|(key, run)| {
+                fonts
+                    .get(key)
+                    .unwrap()
+                    .text_height(run, self.current_font_size())
+            })
+            .sum()
+    }
+
+    /// The ascent of the current font at the current size
+    #[allow(dead_code)]
+    pub fn ascent(&self) -> f32 {
+        self.current_font_ref().ascent(self.current_font_size())
+    }
+
+    /// The descent of the current font at the current size
+    #[allow(dead_code)]
+    pub fn descent(&self) -> f32 {
+        self.current_font_ref().descent(self.current_font_size())
+    }
+
     /// Move to start of the next line (`Td`)
     ///
     /// See section 9.4.2 of the PDF spec
@@ -143,46 +377,197 @@ impl<'a> ContentBuilder<'a> {
     /// Set the text leading (`TL`)
     ///
     /// See section 9.3.1 of the PDF spec
+    #[allow(dead_code)]
     pub fn leading(self, l: u32) -> Self {
         self.push("TL", vec![l.into()])
     }
 
-    /// Set nonstroking colour (`rg`)
+    /// Set nonstroking colour (`g`, `rg`, `k`, or `cs` and `scn`)
     ///
+    /// Colours in a colour space from the resources select that colour space
+    /// first, see [Resources::set_colour_space].
     /// See section 8.6.8 of the PDF spec
     pub fn colour(self, c: Colour) -> Self {
-        self.push("rg", vec![c.0.into(), c.1.into(), c.2.into()])
+        self.set_colour(c, ["g", "rg", "k", "cs", "scn"])
     }
 
-    /// Set stroke colour (`RG`)
+    /// Set stroke colour (`G`, `RG`, `K`, or `CS` and `SCN`)
     ///
     /// See section 8.6.8 of the PDF spec
     pub fn scolour(self, c: Colour) -> Self {
-        self.push("RG", vec![c.0.into(), c.1.into(), c.2.into()])
+        self.set_colour(c, ["G", "RG", "K", "CS", "SCN"])
+    }
+
+    fn set_colour(self, c: Colour, operators: [&str; 5]) -> Self {
+        let [gray, rgb, cmyk, space, colour] = operators;
+        let components = c.components().into_iter().map(Object::Real).collect();
+        let Some(key) = c.resource_key() else {
+            let operator = match c {
+                Colour::Gray(_) => gray,
+                Colour::Rgb(..) => rgb,
+                _ => cmyk,
+            };
+            return self.push(operator, components);
+        };
+        match self.resources.colour_spaces.get(key) {
+            None => panic!("Attempt to use unknown colour space {}", key),
+            Some(colour_space) => {
+                let expected = match (colour_space, c) {
+                    (ColourSpace::IccBased { .. }, Colour::IccRgb(..) | Colour::IccCmyk(..)) => {
+                        colour_space.components()
+                    }
+                    (ColourSpace::Separation { .. }, Colour::Separation(..)) => 1,
+                    _ => 0,
+                };
+                if expected != c.components().len() {
+                    panic!(
+                        "Colour {:?} does not match colour space {:?}",
+                        c, colour_space
+                    );
+                }
+            }
+        }
+        self.push(space, vec![key.into()]).push(colour, components)
     }
 
     /// Show text string as array (`TJ`)
     ///
-    /// See section 9.4.3 of the PDF spec
+    /// With a font stack the strings are split into runs as for
+    /// [ContentBuilder::text], and a new array is started for each change of
+    /// font. See section 9.4.3 of the PDF spec
     #[allow(dead_code)]
-    pub fn text_items(self, text: Vec<TextItem>) -> Self {
-        let args = vec![Object::Array(
-            text.into_iter()
-                .flat_map(|t| match t {
-                    TextItem::String(s) => self.current_font_ref().render_text(s),
-                    TextItem::Position(_) => vec![Into::<Object>::into(t)],
-                })
-                .collect::<Vec<Object>>(),
-        )];
-        self.push("TJ", args)
+    pub fn text_items(mut self, text: Vec<TextItem>) -> Self {
+        let resources = self.resources;
+        let font = self.current_font_key().to_owned();
+        let mut array_font = None;
+        let mut items = vec![];
+        for item in text {
+            match item {
+                TextItem::String(s) => {
+                    for (key, run) in resources.fonts.runs(&font, s) {
+                        if array_font != Some(key) {
+                            if !items.is_empty() {
+                                self = self.push("TJ", vec![Object::Array(items)]);
+                                items = vec![];
+                            }
+                            self = self.select_font(key);
+                            array_font = Some(key);
+                        }
+                        items.extend(resources.fonts.get(key).unwrap().render_text(run));
+                    }
+                }
+                TextItem::Position(_) => items.push(item.into()),
+            }
+        }
+        if items.is_empty() {
+            self
+        } else {
+            self.push("TJ", vec![Object::Array(items)])
+        }
     }
 
     /// Show text string (`Tj`)
     ///
+    /// Fonts with shaping enabled show the text with `TJ` instead. With a font
+    /// stack the text is split into runs, each shown in the first font that
+    /// covers it with its own `Tf`.
     /// See section 9.4.3 of the PDF spec
+    ///
+    /// Panics if the font can not encode the text, use
+    /// [ContentBuilder::try_text] to handle that.
     pub fn text(self, text: &str) -> Self {
-        let text = self.current_font_ref().render_text(text);
-        self.push("Tj", text)
+        self.try_text(text).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Show text string (`Tj`), returning an error if the font can not encode
+    /// it
+    ///
+    /// Only simple fonts can fail: the standard 14 fonts for characters
+    /// outside of their encoding, and TrueType and colour fonts once all of
+    /// their single byte codes are used.
+    pub fn try_text(mut self, text: &str) -> Result<Self, EncodingError> {
+        let resources = self.resources;
+        let font = self.current_font_key().to_owned();
+        for (key, run) in resources.fonts.runs(&font, text) {
+            self = self.select_font(key);
+            let operations = resources
+                .fonts
+                .get(key)
+                .unwrap()
+                .try_render_operations(run)?;
+            self.operations.extend(operations);
+        }
+        Ok(self)
+    }
+
+    /// Show a paragraph of text wrapped to its box
+    ///
+    /// Writes its own text object (`BT` to `ET`). Panics if the text does not
+    /// fit in the box, use [Paragraph::layout] to handle overflow. Paragraphs
+    /// are laid out in a single font so can not use a font stack.
+    pub fn paragraph(mut self, paragraph: &Paragraph) -> Self {
+        let fonts = &self.resources.fonts;
+        let font = fonts.get(paragraph.font()).unwrap_or_else(|| {
+            if fonts.contains_key(paragraph.font()) {
+                panic!(
+                    "Paragraphs can not use the font stack {}, use a single font",
+                    paragraph.font()
+                );
+            }
+            panic!("Font {} does not exist in font map", paragraph.font())
+        });
+        let layout = paragraph.layout(font.as_ref());
+        if let Some(overflow) = layout.overflow {
+            panic!(
+                "Paragraph does not fit in its box, {:?} overflows",
+                overflow
+            );
+        }
+        self.operations.extend(layout.operations(font.as_ref()));
+        self.active_font = Some((paragraph.font().to_owned(), paragraph.size()));
+        self
+    }
+
+    /// Show text in vertical columns, read top to bottom and right to left
+    ///
+    /// The first column hangs from `(x, y)`, the top of its centre line, and
+    /// each following column is `spacing` further left. Columns break on `\n`
+    /// and before any character that would take them past `height`. Writes
+    /// its own text object (`BT` to `ET`) in the current font, which must be
+    /// vertical. See section 9.7.4.3 of the PDF spec.
+    #[allow(dead_code)]
+    pub fn text_columns(mut self, text: &str, x: f32, y: f32, height: f32, spacing: f32) -> Self {
+        if !self.current_font_ref().is_vertical() {
+            panic!(
+                "Font {} is not vertical, use `vertical` on the font builder",
+                self.current_font_key()
+            );
+        }
+        let mut columns = Vec::new();
+        for line in text.split('\n') {
+            let (mut start, mut column_height) = (0, 0.);
+            for (i, c) in line.char_indices() {
+                let c_height = self.text_height(&line[i..i + c.len_utf8()]);
+                if column_height + c_height > height && i > start {
+                    columns.push(&line[start..i]);
+                    (start, column_height) = (i, 0.);
+                }
+                column_height += c_height;
+            }
+            columns.push(&line[start..]);
+        }
+
+        let (font, size) = self.current_font.clone().unwrap();
+        self = self.begin_text().font(&font, size);
+        for (i, column) in columns.into_iter().enumerate() {
+            let position = if i == 0 {
+                vec![x.into(), y.into()]
+            } else {
+                vec![(-spacing).into(), 0.into()]
+            };
+            self = self.push("Td", position).text(column);
+        }
+        self.end_text()
     }
 
     /// Save current graphics state (`q`)
@@ -195,7 +580,9 @@ impl<'a> ContentBuilder<'a> {
     /// Restore graphics state (`Q`)
     ///
     /// See section 8.4.4 of the PDF spec
-    pub fn restore_graphics_state(self) -> Self {
+    pub fn restore_graphics_state(mut self) -> Self {
+        // the restored state may have a different font selected
+        self.active_font = None;
         self.pushe("Q")
     }
 
@@ -244,6 +631,7 @@ impl<'a> ContentBuilder<'a> {
         self.modify_trans_matrix(x, 0, 0, y, 0, 0)
     }
 
+    #[allow(dead_code)]
     pub fn cm_rotate(self, q: f32) -> Self {
         let rc = q.cos();
         let rs = q.sin();
@@ -262,6 +650,123 @@ impl<'a> ContentBuilder<'a> {
         self.push("Do", vec![key.into()])
     }
 
+    /// Set parameters from a graphics state dictionary (`gs`)
+    ///
+    /// The graphics state must have been added to the resources, see
+    /// [Resources::add_graphics_state].
+    /// See section 8.4.5 of the PDF spec
+    pub fn graphics_state(self, state: &GraphicsState) -> Self {
+        let name = self
+            .resources
+            .graphics_states
+            .name(state)
+            .unwrap_or_else(|| panic!("Attempt to set unknown graphics state {:?}", state));
+        self.push("gs", vec![Object::Name(name.into_bytes())])
+    }
+
+    /// Paint a shading over the current clipping region (`sh`)
+    ///
+    /// Clip to a path first, see [ContentBuilder::clip_path], or the shading
+    /// fills the whole page. The shading must have been added to the
+    /// resources, see [Resources::add_shading].
+    /// See section 8.7.4.2 of the PDF spec
+    pub fn shading(self, shading: &Shading) -> Self {
+        let name = self
+            .resources
+            .shadings
+            .name(shading)
+            .unwrap_or_else(|| panic!("Attempt to paint unknown shading {:?}", shading));
+        self.push("sh", vec![Object::Name(name.into_bytes())])
+    }
+
+    /// Set the nonstroking colour to a pattern (`cs` and `scn`)
+    ///
+    /// The pattern must have been added to the resources, see
+    /// [Resources::add_pattern]. Uncoloured patterns need a colour as well,
+    /// see [ContentBuilder::fill_uncoloured_pattern].
+    /// See section 8.7.3.2 of the PDF spec
+    pub fn fill_pattern(self, pattern: &Pattern) -> Self {
+        let name = self.pattern_name(pattern, false);
+        self.push("cs", vec!["Pattern".into()])
+            .push("scn", vec![Object::Name(name.into_bytes())])
+    }
+
+    /// Set the stroking colour to a pattern (`CS` and `SCN`)
+    ///
+    /// See section 8.7.3.2 of the PDF spec
+    #[allow(dead_code)]
+    pub fn stroke_pattern(self, pattern: &Pattern) -> Self {
+        let name = self.pattern_name(pattern, false);
+        self.push("CS", vec!["Pattern".into()])
+            .push("SCN", vec![Object::Name(name.into_bytes())])
+    }
+
+    /// Set the nonstroking colour to an uncoloured pattern painted in a
+    /// colour (`cs` and `scn`)
+    ///
+    /// See section 8.7.3.3 of the PDF spec
+    #[allow(dead_code)]
+    pub fn fill_uncoloured_pattern(self, pattern: &Pattern, c: Colour) -> Self {
+        let (space, operands) = self.uncoloured_pattern_operands(pattern, c);
+        self.push("cs", vec![space.into()]).push("scn", operands)
+    }
+
+    /// Set the stroking colour to an uncoloured pattern painted in a colour
+    /// (`CS` and `SCN`)
+    ///
+    /// See section 8.7.3.3 of the PDF spec
+    pub fn stroke_uncoloured_pattern(self, pattern: &Pattern, c: Colour) -> Self {
+        let (space, operands) = self.uncoloured_pattern_operands(pattern, c);
+        self.push("CS", vec![space.into()]).push("SCN", operands)
+    }
+
+    /// The pattern colour space for the colour's device space, and the colour
+    /// components followed by the pattern name.
+    ///
+    /// Uncoloured patterns can only be painted in device colours.
+    fn uncoloured_pattern_operands(
+        &self,
+        pattern: &Pattern,
+        c: Colour,
+    ) -> (&'static str, Vec<Object>) {
+        let name = self.pattern_name(pattern, true);
+        let space = c
+            .device_space()
+            .and_then(|device| {
+                UNCOLOURED_PATTERN_SPACES
+                    .iter()
+                    .find(|(_, base)| *base == device)
+            })
+            .map(|(space, _)| *space)
+            .unwrap_or_else(|| {
+                panic!(
+                    "Uncoloured patterns can only be painted in device colours but were {:?}",
+                    c
+                )
+            });
+        let mut operands: Vec<Object> = c.components().into_iter().map(Object::Real).collect();
+        operands.push(Object::Name(name.into_bytes()));
+        (space, operands)
+    }
+
+    fn pattern_name(&self, pattern: &Pattern, uncoloured: bool) -> String {
+        if pattern.is_uncoloured() != uncoloured {
+            panic!(
+                "Attempt to use {} pattern as {}",
+                if uncoloured {
+                    "a coloured"
+                } else {
+                    "an uncoloured"
+                },
+                if uncoloured { "uncoloured" } else { "coloured" },
+            );
+        }
+        self.resources
+            .patterns
+            .name(pattern)
+            .unwrap_or_else(|| panic!("Attempt to use unknown pattern {:?}", pattern))
+    }
+
     /// Set line width (`w`)
     ///
     /// See section 8.4.4 of the PDF spec
@@ -269,6 +774,51 @@ impl<'a> ContentBuilder<'a> {
         self.push("w", vec![Number::as_object(w)])
     }
 
+    /// Set line cap style (`J`)
+    ///
+    /// See section 8.4.3.3 of the PDF spec
+    pub fn line_cap(self, cap: LineCap) -> Self {
+        self.push("J", vec![(cap as i64).into()])
+    }
+
+    /// Set line join style (`j`)
+    ///
+    /// See section 8.4.3.4 of the PDF spec
+    pub fn line_join(self, join: LineJoin) -> Self {
+        self.push("j", vec![(join as i64).into()])
+    }
+
+    /// Set miter limit (`M`)
+    ///
+    /// See section 8.4.3.5 of the PDF spec
+    pub fn miter_limit<T: Into<Number>>(self, limit: T) -> Self {
+        self.push("M", vec![Number::as_object(limit)])
+    }
+
+    /// Set line dash pattern (`d`)
+    ///
+    /// The lengths alternate between dashes and gaps, and an empty pattern
+    /// draws solid lines. Lengths can not be negative or all zero.
+    /// See section 8.4.3.6 of the PDF spec
+    pub fn dash_pattern(self, lengths: &[f32], phase: f32) -> Self {
+        if lengths.iter().any(|&l| l < 0.)
+            || (!lengths.is_empty() && lengths.iter().all(|&l| l == 0.))
+        {
+            panic!("Invalid dash pattern {:?}", lengths);
+        }
+        let array = lengths.iter().map(|&l| l.into()).collect::<Vec<Object>>();
+        self.push("d", vec![array.into(), phase.into()])
+    }
+
+    /// Set every line style parameter (`w`, `J`, `j`, `M` and `d`)
+    pub fn line_style(self, style: &LineStyle) -> Self {
+        self.line_width(style.width)
+            .line_cap(style.cap)
+            .line_join(style.join)
+            .miter_limit(style.miter_limit)
+            .dash_pattern(&style.dash, style.dash_phase)
+    }
+
     /// Begin path (`m`)
     ///
     /// See section 8.5.2
@@ -334,6 +884,135 @@ impl<'a> ContentBuilder<'a> {
         self.pushe("B")
     }
 
+    /// Fill path using the even-odd rule (`f*`)
+    ///
+    /// Areas where subpaths overlap an even number of times are left unfilled,
+    /// such as the hole in a ring. See section 8.5.3.3.3 of the PDF spec
+    pub fn fill_path_even_odd(self) -> Self {
+        self.pushe("f*")
+    }
+
+    /// Fill, using the even-odd rule, and stroke path (`B*`)
+    ///
+    /// See section 8.5.3.3.3 of the PDF spec
+    pub fn fill_stroke_path_even_odd(self) -> Self {
+        self.pushe("B*")
+    }
+
+    /// End path without filling or stroking it (`n`)
+    ///
+    /// See section 8.5.3.1 of the PDF spec
+    pub fn end_path(self) -> Self {
+        self.pushe("n")
+    }
+
+    /// Intersect the clipping path with the current path and end it (`W n`)
+    ///
+    /// Everything painted afterwards is clipped until the graphics state is
+    /// restored, so clip between [ContentBuilder::save_graphics_state] and
+    /// [ContentBuilder::restore_graphics_state].
+    /// See section 8.5.4 of the PDF spec
+    pub fn clip_path(self) -> Self {
+        self.pushe("W").end_path()
+    }
+
+    /// Intersect the clipping path with the current path, using the even-odd
+    /// rule, and end it (`W* n`)
+    ///
+    /// See section 8.5.4 of the PDF spec
+    pub fn clip_path_even_odd(self) -> Self {
+        self.pushe("W*").end_path()
+    }
+
+    /// Append a rectangle as a closed subpath (`re`)
+    ///
+    /// See section 8.5.2.1 of the PDF spec
+    pub fn append_rectangle<X: Into<Number>, Y: Into<Number>, W: Into<Number>, H: Into<Number>>(
+        self,
+        x: X,
+        y: Y,
+        width: W,
+        height: H,
+    ) -> Self {
+        self.push(
+            "re",
+            vec![
+                Number::as_object(x),
+                Number::as_object(y),
+                Number::as_object(width),
+                Number::as_object(height),
+            ],
+        )
+    }
+
+    /// Append a rectangle with quarter circle corners as a closed subpath
+    ///
+    /// The radius is limited to half of the shorter side.
+    pub fn append_rounded_rectangle(
+        self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+    ) -> Self {
+        let r = radius.min(width / 2.).min(height / 2.);
+        let k = r * BEZIER_CIRCLE;
+        let (right, top) = (x + width, y + height);
+        self.begin_path(x + r, y)
+            .append_straight_line(right - r, y)
+            .append_curve(right - r + k, y, right, y + r - k, right, y + r)
+            .append_straight_line(right, top - r)
+            .append_curve(right, top - r + k, right - r + k, top, right - r, top)
+            .append_straight_line(x + r, top)
+            .append_curve(x + r - k, top, x, top - r + k, x, top - r)
+            .append_straight_line(x, y + r)
+            .append_curve(x, y + r - k, x + r - k, y, x + r, y)
+            .close_subpath()
+    }
+
+    /// Append an ellipse centred on `(x, y)` as a closed subpath
+    ///
+    /// Each quarter is a Bézier curve, see section 8.5.2.2 of the PDF spec.
+    pub fn append_ellipse(self, x: f32, y: f32, radius_x: f32, radius_y: f32) -> Self {
+        let (kx, ky) = (radius_x * BEZIER_CIRCLE, radius_y * BEZIER_CIRCLE);
+        self.begin_path(x + radius_x, y)
+            .append_curve(x + radius_x, y + ky, x + kx, y + radius_y, x, y + radius_y)
+            .append_curve(x - kx, y + radius_y, x - radius_x, y + ky, x - radius_x, y)
+            .append_curve(x - radius_x, y - ky, x - kx, y - radius_y, x, y - radius_y)
+            .append_curve(x + kx, y - radius_y, x + radius_x, y - ky, x + radius_x, y)
+            .close_subpath()
+    }
+
+    /// Clip to a rectangle, see [ContentBuilder::clip_path]
+    pub fn clip_rectangle<X: Into<Number>, Y: Into<Number>, W: Into<Number>, H: Into<Number>>(
+        self,
+        x: X,
+        y: Y,
+        width: W,
+        height: H,
+    ) -> Self {
+        self.append_rectangle(x, y, width, height).clip_path()
+    }
+
+    /// Clip to a rounded rectangle, see [ContentBuilder::clip_path]
+    pub fn clip_rounded_rectangle(
+        self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+    ) -> Self {
+        self.append_rounded_rectangle(x, y, width, height, radius)
+            .clip_path()
+    }
+
+    /// Clip to an ellipse, see [ContentBuilder::clip_path]
+    pub fn clip_ellipse(self, x: f32, y: f32, radius_x: f32, radius_y: f32) -> Self {
+        self.append_ellipse(x, y, radius_x, radius_y).clip_path()
+    }
+
     pub fn build_operations(self) -> Vec<Operation> {
         self.operations
     }
@@ -361,6 +1040,12 @@ impl<'a> ContentBuilder<'a> {
     }
 }
 
+/// The distance of the control points from the ends of a Bézier curve that
+/// approximates a quarter of a unit circle.
+///
+/// See <https://spencermortensen.com/articles/bezier-circle/>
+const BEZIER_CIRCLE: f32 = 0.552_284_8;
+
 pub(crate) enum Number {
     Integer(i64),
     Real(f32),
@@ -417,6 +1102,10 @@ impl IdMap {
         self.0.contains_key(key)
     }
 
+    pub fn get(&self, key: &str) -> Option<ObjectId> {
+        self.0.get(key).copied()
+    }
+
     pub fn as_dictionary(&self) -> Dictionary {
         let mut dict = Dictionary::new();
         for (key, &value) in self.0.iter() {
@@ -429,10 +1118,13 @@ impl IdMap {
 /// A mapping of key to [FontReference]
 ///
 /// This is to build the font dictionary and to look up the correct `FontReference` to use
-/// when encoding text.
+/// when encoding text. Font stacks are named lists of fonts to fall back through
+/// for characters that a font does not cover, they are not written to the
+/// font dictionary.
 #[derive(Default)]
 pub(crate) struct FontMap {
     fonts: HashMap<String, Box<dyn FontReference>>,
+    stacks: HashMap<String, Vec<String>>,
 }
 
 impl FontMap {
@@ -457,8 +1149,26 @@ impl FontMap {
         self.fonts.insert(key.to_owned(), Box::new(value));
     }
 
+    /// Add a font stack made of fonts already in the map.
+    pub fn set_stack(&mut self, key: &str, fonts: &[&str]) {
+        if self.fonts.contains_key(key) {
+            panic!("Font stack {} has the same key as a font", key);
+        }
+        if fonts.is_empty() {
+            panic!("Font stack {} has no fonts", key);
+        }
+        if let Some(font) = fonts.iter().find(|font| !self.fonts.contains_key(**font)) {
+            panic!("Font {} in stack {} does not exist in font map", font, key);
+        }
+        self.stacks.insert(
+            key.to_owned(),
+            fonts.iter().map(|&font| font.to_owned()).collect(),
+        );
+    }
+
+    /// Whether the key is either a font or a font stack.
     pub fn contains_key(&self, key: &str) -> bool {
-        self.fonts.contains_key(key)
+        self.fonts.contains_key(key) || self.stacks.contains_key(key)
     }
 
     #[allow(clippy::borrowed_box)]
@@ -466,6 +1176,47 @@ impl FontMap {
         self.fonts.get(key)
     }
 
+    /// The keys of the fonts in a stack, or just the key for a single font.
+    pub fn stack<'a>(&'a self, key: &'a str) -> Vec<&'a str> {
+        match self.stacks.get(key) {
+            Some(fonts) => fonts.iter().map(String::as_str).collect(),
+            None => vec![key],
+        }
+    }
+
+    /// Split text into runs that are covered by a single font from a stack.
+    ///
+    /// Each character uses the first font in the stack that covers it, except
+    /// for whitespace which stays in the current run if it can. Characters
+    /// that no font covers are left to the first font, which either fails to
+    /// encode them or shows them with `.notdef` and warns when it is finished.
+    pub fn runs<'a, 't>(&'a self, key: &'a str, text: &'t str) -> Vec<(&'a str, &'t str)> {
+        let stack = self.stack(key);
+        let covers = |font: &str, c: char| self.fonts[font].covers(c);
+        let mut runs: Vec<(&str, &str)> = Vec::new();
+        let mut start = 0;
+        let mut current: Option<&str> = None;
+        for (i, c) in text.char_indices() {
+            let font = match current {
+                Some(font) if c.is_whitespace() && covers(font, c) => font,
+                _ => stack
+                    .iter()
+                    .copied()
+                    .find(|font| covers(font, c))
+                    .unwrap_or(stack[0]),
+            };
+            if let Some(previous) = current.filter(|&previous| previous != font) {
+                runs.push((previous, &text[start..i]));
+                start = i;
+            }
+            current = Some(font);
+        }
+        if let Some(font) = current {
+            runs.push((font, &text[start..]));
+        }
+        runs
+    }
+
     pub fn as_dictionary(&self) -> Dictionary {
         let mut dict = Dictionary::new();
         for (key, value) in self.fonts.iter() {
@@ -473,12 +1224,99 @@ impl FontMap {
         }
         dict
     }
+
+    /// Write out the fonts, returning their warnings, see
+    /// [FontReference::finish].
+    pub fn finish(&self, doc: &mut Document) -> Vec<String> {
+        self.fonts
+            .values()
+            .flat_map(|value| value.finish(doc))
+            .collect()
+    }
+}
+
+/// A resource that is named by the order it was added in, such as `GS1`.
+pub(crate) trait NamedResource: PartialEq {
+    const PREFIX: &'static str;
+}
+
+impl NamedResource for GraphicsState {
+    const PREFIX: &'static str = "GS";
+}
+
+impl NamedResource for Shading {
+    const PREFIX: &'static str = "Sh";
+}
+
+impl NamedResource for Pattern {
+    const PREFIX: &'static str = "P";
+}
+
+/// Resources of one category, named as they are added.
+///
+/// Each resource is kept with the object written for it in the resources
+/// dictionary. Adding a resource equal to one that was already added returns
+/// the existing name, so callers can add the same resource without tracking
+/// it.
+pub(crate) struct NamedResources<T>(Vec<(T, Object)>);
+
+impl<T> Default for NamedResources<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
 }
 
+impl<T: NamedResource> NamedResources<T> {
+    pub fn add(&mut self, resource: T, object: Object) -> String {
+        if let Some(name) = self.name(&resource) {
+            return name;
+        }
+        self.0.push((resource, object));
+        format!("{}{}", T::PREFIX, self.0.len())
+    }
+
+    pub fn name(&self, resource: &T) -> Option<String> {
+        self.0
+            .iter()
+            .position(|(r, _)| r == resource)
+            .map(|i| format!("{}{}", T::PREFIX, i + 1))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.0.iter().map(|(resource, _)| resource)
+    }
+
+    pub fn as_dictionary(&self) -> Dictionary {
+        let mut dict = Dictionary::new();
+        for (i, (_, object)) in self.0.iter().enumerate() {
+            dict.set(format!("{}{}", T::PREFIX, i + 1), object.clone());
+        }
+        dict
+    }
+}
+
+/// The colour spaces for uncoloured patterns, by the device colour space they
+/// are painted in.
+///
+/// See section 8.7.3.3 of the PDF spec.
+const UNCOLOURED_PATTERN_SPACES: [(&str, &str); 3] = [
+    ("PatternGray", "DeviceGray"),
+    ("PatternRGB", "DeviceRGB"),
+    ("PatternCMYK", "DeviceCMYK"),
+];
+
 #[derive(Default)]
 pub(crate) struct Resources {
     xobjects: IdMap,
     fonts: FontMap,
+    graphics_states: NamedResources<GraphicsState>,
+    shadings: NamedResources<Shading>,
+    patterns: NamedResources<Pattern>,
+    colour_spaces: HashMap<String, ColourSpace>,
 }
 
 impl Resources {
@@ -486,20 +1324,100 @@ impl Resources {
         self.fonts.set(key, font);
     }
 
+    /// Add a font stack, see [FontMap::set_stack].
+    pub fn set_font_stack(&mut self, key: &str, fonts: &[&str]) {
+        self.fonts.set_stack(key, fonts);
+    }
+
     pub fn set_xobject(&mut self, key: &str, id: ObjectId) {
         self.xobjects.set(key, id);
     }
 
+    pub fn xobject(&self, key: &str) -> Option<ObjectId> {
+        self.xobjects.get(key)
+    }
+
+    /// Add an ICC based or spot colour space for colours to refer to by key,
+    /// see [Colour].
+    pub fn set_colour_space(&mut self, key: &str, colour_space: ColourSpace) {
+        if !key.chars().all(|c| c.is_ascii_alphanumeric())
+            || UNCOLOURED_PATTERN_SPACES
+                .iter()
+                .any(|(space, _)| *space == key)
+        {
+            panic!(
+                "Colour space key must be ASCII alpha numeric and not a pattern space but was {}",
+                key
+            );
+        }
+        self.colour_spaces.insert(key.to_owned(), colour_space);
+    }
+
+    /// Add a graphics state, named `GS1`, `GS2` and so on.
+    ///
+    /// Returns the name, see [NamedResources].
+    pub fn add_graphics_state(&mut self, state: GraphicsState) -> String {
+        let object = state.as_dictionary().into();
+        self.graphics_states.add(state, object)
+    }
+
+    /// Add a shading to paint with `sh`, named `Sh1`, `Sh2` and so on.
+    pub fn add_shading(&mut self, shading: Shading) -> String {
+        let object = shading.as_dictionary().into();
+        self.shadings.add(shading, object)
+    }
+
+    /// Add a pattern to fill or stroke with, named `P1`, `P2` and so on.
+    ///
+    /// Tiling patterns are written to the document when they are first added.
+    pub fn add_pattern(&mut self, doc: &mut Document, pattern: Pattern) -> String {
+        if let Some(name) = self.patterns.name(&pattern) {
+            return name;
+        }
+        let object = pattern.add_to_doc(doc);
+        self.patterns.add(pattern, object)
+    }
+
     pub fn as_dictionary(&self) -> Dictionary {
-        dictionary! {
+        let mut dict = dictionary! {
             "Font" => self.fonts.as_dictionary(),
             "XObject" => self.xobjects.as_dictionary(),
+        };
+        if !self.graphics_states.is_empty() {
+            dict.set("ExtGState", self.graphics_states.as_dictionary());
+        }
+        if !self.shadings.is_empty() {
+            dict.set("Shading", self.shadings.as_dictionary());
+        }
+        if !self.patterns.is_empty() {
+            dict.set("Pattern", self.patterns.as_dictionary());
         }
+        let mut colour_spaces = Dictionary::new();
+        for (key, colour_space) in self.colour_spaces.iter() {
+            colour_spaces.set(key.as_str(), colour_space.as_object());
+        }
+        if self.patterns.iter().any(Pattern::is_uncoloured) {
+            for (space, base) in UNCOLOURED_PATTERN_SPACES {
+                colour_spaces.set(space, vec!["Pattern".into(), base.into()]);
+            }
+        }
+        if !colour_spaces.is_empty() {
+            dict.set("ColorSpace", colour_spaces);
+        }
+        dict
     }
 
     pub fn add_to_doc(&self, doc: &mut Document) -> ObjectId {
         doc.add_object(self.as_dictionary())
     }
+
+    /// Write out anything that depends on the content using these resources.
+    ///
+    /// Must be called after all content streams have been built. Returns
+    /// warnings about anything that will not look as expected.
+    pub fn finish(&self, doc: &mut Document) -> Vec<String> {
+        self.fonts.finish(doc)
+    }
 }
 
 #[cfg(test)]
@@ -507,6 +1425,7 @@ mod tests {
 
     use super::*;
     use crate::fonts::InternalFontReference;
+    use crate::pattern::TilingPattern;
 
     #[test]
     #[should_panic]
@@ -544,6 +1463,392 @@ mod tests {
         )
     }
 
+    #[test]
+    fn text_with_font_stack() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        resources.set_font("F1", crate::fonts::type1("Helvetica").add_to_doc(&mut doc));
+        resources.set_font("F2", crate::fonts::type1("Symbol").add_to_doc(&mut doc));
+        resources.set_font_stack("Text", &["F1", "F2"]);
+        let runs = resources.fonts.runs("Text", "for all ∀ x");
+        assert_eq!(runs, vec![("F1", "for all "), ("F2", "∀ "), ("F1", "x")]);
+
+        let operations = ContentBuilder::new(&resources)
+            .begin_text()
+            .font("Text", 12)
+            .text("a ∀ b")
+            .end_text()
+            .build_operations();
+        let fonts: Vec<_> = operations
+            .iter()
+            .filter(|op| op.operator == "Tf")
+            .map(|op| op.operands[0].as_name_str().unwrap())
+            .collect();
+        assert_eq!(fonts, vec!["F1", "F2", "F1"]);
+        assert!(!resources
+            .as_dictionary()
+            .get(b"Font")
+            .unwrap()
+            .as_dict()
+            .unwrap()
+            .has(b"Text"));
+    }
+
+    #[test]
+    fn text_items_with_font_stack() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        resources.set_font("F1", crate::fonts::type1("Helvetica").add_to_doc(&mut doc));
+        resources.set_font("F2", crate::fonts::type1("Symbol").add_to_doc(&mut doc));
+        resources.set_font_stack("Text", &["F1", "F2"]);
+
+        let operations = ContentBuilder::new(&resources)
+            .begin_text()
+            .font("Text", 12)
+            .text_items(vec![text_item("a"), text_item(-250), text_item("∀ b")])
+            .end_text()
+            .build_operations();
+        let shown: Vec<_> = operations
+            .iter()
+            .filter(|op| op.operator == "Tf" || op.operator == "TJ")
+            .map(|op| match op.operator.as_str() {
+                "Tf" => op.operands[0].as_name_str().unwrap().to_owned(),
+                _ => format!("{} items", op.operands[0].as_array().unwrap().len()),
+            })
+            .collect();
+        assert_eq!(
+            shown,
+            vec!["F1", "2 items", "F2", "1 items", "F1", "1 items"]
+        );
+    }
+
+    #[test]
+    fn font_size_changes_reselect_the_font() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        resources.set_font("F1", crate::fonts::type1("Helvetica").add_to_doc(&mut doc));
+        resources.set_font_stack("Body", &["F1"]);
+        let operations = ContentBuilder::new(&resources)
+            .begin_text()
+            .font("F1", 38)
+            .text("Title")
+            .font("F1", 17)
+            .text("date")
+            .font("Body", 17)
+            .text("body")
+            .end_text()
+            .build_operations();
+        let sizes: Vec<_> = operations
+            .iter()
+            .filter(|op| op.operator == "Tf")
+            .map(|op| op.operands[1].as_i64().unwrap())
+            .collect();
+        // the stack's first font is already selected at the same size
+        assert_eq!(sizes, vec![38, 17]);
+    }
+
+    #[test]
+    fn text_the_font_can_not_encode() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        resources.set_font("F1", crate::fonts::type1("Helvetica").add_to_doc(&mut doc));
+        let builder = ContentBuilder::new(&resources).begin_text().font("F1", 12);
+        let err = builder.try_text("1 → 2").err().unwrap();
+        assert_eq!(err.character, '→');
+        assert_eq!(
+            err.to_string(),
+            "'→' (U+2192) can not be encoded in the font Helvetica"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can not use the font stack Text")]
+    fn paragraph_with_font_stack() {
+        let mut resources = Resources::default();
+        resources.set_font("F1", InternalFontReference::default());
+        resources.set_font_stack("Text", &["F1"]);
+        ContentBuilder::new(&resources).paragraph(&Paragraph::new("Some text", "Text", 12));
+    }
+
+    #[test]
+    #[should_panic]
+    fn font_stack_with_missing_font() {
+        let mut resources = Resources::default();
+        resources.set_font("F1", InternalFontReference::default());
+        resources.set_font_stack("Text", &["F1", "F2"]);
+    }
+
+    #[test]
+    fn measure_text() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        resources.set_font("F1", crate::fonts::type1("Helvetica").add_to_doc(&mut doc));
+        let builder = ContentBuilder::new(&resources).font("F1", 10);
+
+        // space is 278 and W is 944 in Helvetica
+        assert_eq!(builder.text_width(" W"), 12.22);
+        assert_eq!(builder.ascent(), 7.18);
+        assert_eq!(builder.descent(), -2.07);
+    }
+
+    #[test]
+    fn text_in_vertical_columns() {
+        let mut doc = Document::with_version("1.5");
+        let mut resources = Resources::default();
+        let font = crate::fonts::FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .vertical(true)
+            .add_to_doc(&mut doc);
+        resources.set_font("F1", font);
+
+        // without vertical metrics each glyph is ascender - descender, 11.36
+        let operations = ContentBuilder::new(&resources)
+            .font("F1", 10)
+            .text_columns("abcde\nf", 100., 200., 25., 14.)
+            .build_operations();
+        let positions: Vec<Vec<f32>> = operations
+            .iter()
+            .filter(|op| op.operator == "Td")
+            .map(|op| op.operands.iter().map(|o| o.as_float().unwrap()).collect())
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                vec![100., 200.],
+                vec![-14., 0.],
+                vec![-14., 0.],
+                vec![-14., 0.]
+            ]
+        );
+        let shown = operations.iter().filter(|op| op.operator == "Tj").count();
+        assert_eq!(shown, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not vertical")]
+    fn text_columns_with_horizontal_font() {
+        let mut resources = Resources::default();
+        resources.set_font("F1", InternalFontReference::default());
+        ContentBuilder::new(&resources)
+            .font("F1", 10)
+            .text_columns("abc", 0., 0., 100., 12.);
+    }
+
+    #[test]
+    fn clip_to_shapes() {
+        let resources = Resources::default();
+        let operators = |b: ContentBuilder| -> String {
+            b.build_operations()
+                .into_iter()
+                .map(|op| op.operator)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        let b = ContentBuilder::new(&resources).clip_rectangle(0, 0, 100, 50);
+        assert_eq!(operators(b), "re W n");
+        let b = ContentBuilder::new(&resources).clip_ellipse(50., 50., 20., 10.);
+        assert_eq!(operators(b), "m c c c c h W n");
+        let b = ContentBuilder::new(&resources)
+            .append_ellipse(0., 0., 20., 20.)
+            .append_ellipse(0., 0., 10., 10.)
+            .clip_path_even_odd();
+        assert!(operators(b).ends_with("h W* n"));
+
+        // the radius is limited to half the height, making a pill shape
+        let operations = ContentBuilder::new(&resources)
+            .clip_rounded_rectangle(10., 10., 100., 20., 15.)
+            .build_operations();
+        let ends: Vec<Vec<f32>> = operations
+            .iter()
+            .filter(|op| op.operator == "l" || op.operator == "m")
+            .map(|op| op.operands.iter().map(|o| o.as_float().unwrap()).collect())
+            .collect();
+        assert_eq!(
+            ends,
+            vec![
+                vec![20., 10.],
+                vec![100., 10.],
+                vec![110., 20.],
+                vec![20., 30.],
+                vec![10., 20.]
+            ]
+        );
+    }
+
+    #[test]
+    fn set_line_style() {
+        let resources = Resources::default();
+        let style = LineStyle::new(2.)
+            .cap(LineCap::Round)
+            .join(LineJoin::Bevel)
+            .dash(&[3., 1.], 1.);
+        let operations = ContentBuilder::new(&resources)
+            .line_style(&style)
+            .build_operations();
+        let content = Content { operations }.encode().unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "2 w\n1 J\n2 j\n10 M\n[3 1] 1 d"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid dash pattern")]
+    fn dash_pattern_of_zeros() {
+        let resources = Resources::default();
+        ContentBuilder::new(&resources).dash_pattern(&[0., 0.], 0.);
+    }
+
+    #[test]
+    fn graphics_states_are_named_in_order() {
+        let mut resources = Resources::default();
+        let translucent = GraphicsState::new().fill_alpha(0.5);
+        let faint = GraphicsState::new().stroke_alpha(0.2);
+        assert_eq!(resources.add_graphics_state(translucent.clone()), "GS1");
+        assert_eq!(resources.add_graphics_state(faint.clone()), "GS2");
+        assert_eq!(resources.add_graphics_state(translucent.clone()), "GS1");
+
+        let operations = ContentBuilder::new(&resources)
+            .graphics_state(&faint)
+            .build_operations();
+        assert_eq!(operations[0].operator, "gs");
+        assert_eq!(operations[0].operands[0].as_name_str().unwrap(), "GS2");
+        let dict = resources.as_dictionary();
+        let states = dict.get(b"ExtGState").unwrap().as_dict().unwrap();
+        assert_eq!(states.len(), 2);
+    }
+
+    #[test]
+    fn paint_shadings_and_patterns() {
+        let mut resources = Resources::default();
+        let shading = Shading::axial(
+            (0., 0.),
+            (10., 0.),
+            &[(0., Colour::Rgb(1., 1., 1.)), (1., Colour::Rgb(0., 0., 0.))],
+        );
+        assert_eq!(resources.add_shading(shading.clone()), "Sh1");
+        let pattern = Pattern::Shading(shading.clone());
+        let mut doc = Document::with_version("1.7");
+        assert_eq!(resources.add_pattern(&mut doc, pattern.clone()), "P1");
+
+        let operations = ContentBuilder::new(&resources)
+            .fill_pattern(&pattern)
+            .clip_rectangle(0, 0, 10, 10)
+            .shading(&shading)
+            .build_operations();
+        let content = Content { operations }.encode().unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "/Pattern cs\n/P1 scn\n0 0 10 10 re\nW\nn\n/Sh1 sh"
+        );
+        let dict = resources.as_dictionary();
+        assert!(dict.get(b"Shading").unwrap().as_dict().unwrap().has(b"Sh1"));
+        assert!(dict.get(b"Pattern").unwrap().as_dict().unwrap().has(b"P1"));
+        assert!(dict.get(b"ColorSpace").is_err());
+    }
+
+    fn hatching() -> Pattern {
+        Pattern::Tiling(TilingPattern::uncoloured(4., 4., |b| {
+            b.draw_line((0, 0), (4, 4)).stroke_path()
+        }))
+    }
+
+    #[test]
+    fn paint_uncoloured_patterns() {
+        let mut resources = Resources::default();
+        let mut doc = Document::with_version("1.7");
+        assert_eq!(resources.add_pattern(&mut doc, hatching()), "P1");
+        assert_eq!(resources.add_pattern(&mut doc, hatching()), "P1");
+        // the pattern stream is only written once
+        assert_eq!(doc.objects.len(), 1);
+
+        let operations = ContentBuilder::new(&resources)
+            .fill_uncoloured_pattern(&hatching(), Colour::Rgb(1., 0., 0.))
+            .build_operations();
+        let content = Content { operations }.encode().unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "/PatternRGB cs\n1 0 0 /P1 scn"
+        );
+        let dict = resources.as_dictionary();
+        let spaces = dict.get(b"ColorSpace").unwrap().as_dict().unwrap();
+        let space = spaces.get(b"PatternRGB").unwrap().as_array().unwrap();
+        assert_eq!(space[1].as_name_str().unwrap(), "DeviceRGB");
+        let space = spaces.get(b"PatternCMYK").unwrap().as_array().unwrap();
+        assert_eq!(space[1].as_name_str().unwrap(), "DeviceCMYK");
+
+        let operations = ContentBuilder::new(&resources)
+            .stroke_uncoloured_pattern(&hatching(), Colour::Gray(0.5))
+            .build_operations();
+        let content = Content { operations }.encode().unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "/PatternGray CS\n0.5 /P1 SCN"
+        );
+        assert!(dict
+            .get(b"Pattern")
+            .unwrap()
+            .as_dict()
+            .unwrap()
+            .get(b"P1")
+            .unwrap()
+            .as_reference()
+            .is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "uncoloured pattern as coloured")]
+    fn fill_uncoloured_pattern_without_colour() {
+        let mut resources = Resources::default();
+        resources.add_pattern(&mut Document::with_version("1.7"), hatching());
+        ContentBuilder::new(&resources).fill_pattern(&hatching());
+    }
+
+    #[test]
+    fn colours_choose_their_operators() {
+        let mut resources = Resources::default();
+        resources.set_colour_space(
+            "Spot",
+            ColourSpace::separation("Brand", Colour::Cmyk(0., 1., 1., 0.)),
+        );
+        let operations = ContentBuilder::new(&resources)
+            .colour(Colour::Gray(0.5))
+            .scolour(Colour::Cmyk(0., 0., 0., 1.))
+            .colour(Colour::Separation("Spot", 0.5))
+            .build_operations();
+        let content = Content { operations }.encode().unwrap();
+        assert_eq!(
+            String::from_utf8(content).unwrap(),
+            "0.5 g\n0 0 0 1 K\n/Spot cs\n0.5 scn"
+        );
+        let dict = resources.as_dictionary();
+        let spaces = dict.get(b"ColorSpace").unwrap().as_dict().unwrap();
+        assert!(spaces.get(b"Spot").unwrap().as_array().is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match colour space")]
+    fn colour_in_the_wrong_colour_space() {
+        let mut resources = Resources::default();
+        resources.set_colour_space("Spot", ColourSpace::separation("Brand", Colour::Gray(0.)));
+        ContentBuilder::new(&resources).colour(Colour::IccRgb("Spot", 1., 0., 0.));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown colour space")]
+    fn colour_space_not_in_resources() {
+        let resources = Resources::default();
+        ContentBuilder::new(&resources).scolour(Colour::Separation("Spot", 1.));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown graphics state")]
+    fn set_graphics_state_not_in_resources() {
+        let resources = Resources::default();
+        ContentBuilder::new(&resources).graphics_state(&GraphicsState::new().fill_alpha(0.5));
+    }
+
     #[test]
     #[should_panic]
     fn add_text_without_font() {
@@ -0,0 +1,369 @@
+//! Colour glyphs
+//!
+//! Emoji fonts draw their glyphs in colour, either as layers of outlines
+//! filled from a palette (`COLR` and `CPAL`) or as embedded bitmaps (`sbix`
+//! and `CBDT`). PDF fonts only have one colour, so each colour glyph is drawn
+//! into a Form XObject in glyph space which is then painted by the glyph
+//! procedure of a Type3 font. See section 8.10 of the PDF spec.
+//!
+//! Only the first palette is used and transparency in palette colours is
+//! ignored. Bitmaps must be PNGs, their alpha channel becomes a soft mask.
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+use owned_ttf_parser::{colr, Face, GlyphId, OutlineBuilder, RasterImageFormat, RgbaColor};
+
+/// The bitmap size, in pixels per em, to pick a strike for.
+///
+/// Large enough to stay sharp on slides when emoji are shown at heading sizes.
+const STRIKE_SIZE: u16 = 160;
+
+/// Whether the font has any colour glyph tables.
+pub(crate) fn has_colour_glyphs(face: &Face) -> bool {
+    let tables = face.tables();
+    tables.colr.is_some() || tables.sbix.is_some() || tables.cbdt.is_some()
+}
+
+/// Whether a glyph is drawn in colour.
+pub(crate) fn is_colour_glyph(face: &Face, glyph_id: u16) -> bool {
+    let gid = GlyphId(glyph_id);
+    face.is_color_glyph(gid) || face.glyph_raster_image(gid, STRIKE_SIZE).is_some()
+}
+
+/// Draw a glyph into a Form XObject, in font units.
+///
+/// Glyphs without a colour version are filled with their outline in the
+/// current fill colour. Returns `None` for glyphs with nothing to draw, such
+/// as spaces, and an error saying why for bitmaps that can not be drawn.
+pub(crate) fn glyph_form(
+    doc: &mut Document,
+    face: &Face,
+    glyph_id: u16,
+) -> Result<Option<Stream>, String> {
+    let gid = GlyphId(glyph_id);
+    if !is_colour_glyph(face, glyph_id) {
+        let mut builder = PathBuilder::default();
+        let Some(bbox) = face.outline_glyph(gid, &mut builder) else {
+            return Ok(None);
+        };
+        let mut operations = builder.operations;
+        operations.push(Operation::new("f", vec![]));
+        let bbox = [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max].map(f32::from);
+        return Ok(Some(form(bbox, operations, None)));
+    }
+    if face.is_color_glyph(gid) {
+        let mut painter = LayerPainter {
+            face,
+            path: vec![],
+            operations: vec![],
+        };
+        if face.paint_color_glyph(gid, 0, &mut painter).is_none() {
+            return Ok(None);
+        }
+        let bbox = face.global_bounding_box();
+        let bbox = [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max].map(f32::from);
+        return Ok(Some(form(bbox, painter.operations, None)));
+    }
+
+    let Some(image) = face.glyph_raster_image(gid, STRIKE_SIZE) else {
+        return Ok(None);
+    };
+    if image.format != RasterImageFormat::PNG {
+        return Err(format!(
+            "only PNG colour glyphs are supported, glyph {glyph_id} is not drawn"
+        ));
+    }
+    let Some(image_stream) = decode_png(doc, image.data) else {
+        return Err(format!(
+            "could not decode the PNG for colour glyph {glyph_id}"
+        ));
+    };
+    let image_id = doc.add_object(image_stream);
+
+    // bitmap metrics are in pixels of the strike
+    let scale = face.units_per_em() as f32 / image.pixels_per_em as f32;
+    let (x, y) = (image.x as f32 * scale, image.y as f32 * scale);
+    let (width, height) = (image.width as f32 * scale, image.height as f32 * scale);
+    let operations = vec![
+        Operation::new("q", vec![]),
+        Operation::new(
+            "cm",
+            vec![
+                width.into(),
+                0.into(),
+                0.into(),
+                height.into(),
+                x.into(),
+                y.into(),
+            ],
+        ),
+        Operation::new("Do", vec!["Im".into()]),
+        Operation::new("Q", vec![]),
+    ];
+    let resources = dictionary! { "XObject" => dictionary! { "Im" => image_id } };
+    Ok(Some(form(
+        [x, y, x + width, y + height],
+        operations,
+        Some(resources),
+    )))
+}
+
+fn form(
+    bbox: [f32; 4],
+    operations: Vec<Operation>,
+    resources: Option<lopdf::Dictionary>,
+) -> Stream {
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Form",
+        "BBox" => bbox.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+    };
+    if let Some(resources) = resources {
+        dict.set("Resources", resources);
+    }
+    let content = Content { operations };
+    Stream::new(dict, content.encode().unwrap()).with_compression(true)
+}
+
+/// Decode a PNG into an RGB image with its alpha channel as a soft mask.
+///
+/// See section 11.6.5.3 of the PDF spec.
+fn decode_png(doc: &mut Document, data: &[u8]) -> Option<Stream> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info().ok()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer).ok()?;
+    let pixels = &buffer[..info.buffer_size()];
+
+    let (rgb, alpha): (Vec<u8>, Vec<u8>) = match info.color_type {
+        png::ColorType::Rgba => (
+            pixels.chunks(4).flat_map(|p| [p[0], p[1], p[2]]).collect(),
+            pixels.chunks(4).map(|p| p[3]).collect(),
+        ),
+        png::ColorType::Rgb => (pixels.to_vec(), vec![]),
+        png::ColorType::GrayscaleAlpha => (
+            pixels.chunks(2).flat_map(|p| [p[0]; 3]).collect(),
+            pixels.chunks(2).map(|p| p[1]).collect(),
+        ),
+        png::ColorType::Grayscale => (pixels.iter().flat_map(|&p| [p; 3]).collect(), vec![]),
+        png::ColorType::Indexed => return None,
+    };
+
+    let image = |colour_space: &str, data: Vec<u8>| {
+        Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => info.width,
+                "Height" => info.height,
+                "ColorSpace" => colour_space,
+                "BitsPerComponent" => 8,
+            },
+            data,
+        )
+        .with_compression(true)
+    };
+    let mut stream = image("DeviceRGB", rgb);
+    if alpha.iter().any(|&a| a != 255) {
+        let mask_id = doc.add_object(image("DeviceGray", alpha));
+        stream.dict.set("SMask", mask_id);
+    }
+    Some(stream)
+}
+
+/// Collects the layers of a `COLR` glyph as filled paths.
+struct LayerPainter<'a> {
+    face: &'a Face<'a>,
+    path: Vec<Operation>,
+    operations: Vec<Operation>,
+}
+
+impl colr::Painter for LayerPainter<'_> {
+    fn outline(&mut self, glyph_id: GlyphId) {
+        let mut builder = PathBuilder::default();
+        self.face.outline_glyph(glyph_id, &mut builder);
+        self.path = builder.operations;
+    }
+
+    /// Fill with the text colour, which is the fill colour when the glyph is shown.
+    fn paint_foreground(&mut self) {
+        self.operations.append(&mut self.path);
+        self.operations.push(Operation::new("f", vec![]));
+    }
+
+    fn paint_color(&mut self, color: RgbaColor) {
+        let component = |c: u8| Object::Real(c as f32 / 255.);
+        self.operations.push(Operation::new("q", vec![]));
+        self.operations.push(Operation::new(
+            "rg",
+            vec![
+                component(color.red),
+                component(color.green),
+                component(color.blue),
+            ],
+        ));
+        self.operations.append(&mut self.path);
+        self.operations.push(Operation::new("f", vec![]));
+        self.operations.push(Operation::new("Q", vec![]));
+    }
+}
+
+/// Converts a glyph outline to path construction operators.
+///
+/// Quadratic curves are raised to cubic curves as PDF paths only have cubic
+/// Bézier curves. See section 8.5.2 of the PDF spec.
+#[derive(Default)]
+struct PathBuilder {
+    operations: Vec<Operation>,
+    current: (f32, f32),
+}
+
+impl PathBuilder {
+    fn push(&mut self, operator: &str, points: &[f32]) {
+        let operands = points.iter().map(|&p| Object::Real(p)).collect();
+        self.operations.push(Operation::new(operator, operands));
+        if let [.., x, y] = points {
+            self.current = (*x, *y);
+        }
+    }
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.push("m", &[x, y]);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.push("l", &[x, y]);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x0, y0) = self.current;
+        self.push(
+            "c",
+            &[
+                x0 + 2. / 3. * (x1 - x0),
+                y0 + 2. / 3. * (y1 - y0),
+                x + 2. / 3. * (x1 - x),
+                y + 2. / 3. * (y1 - y),
+                x,
+                y,
+            ],
+        );
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.push("c", &[x1, y1, x2, y2, x, y]);
+    }
+
+    fn close(&mut self) {
+        self.push("h", &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::fonts::subset;
+
+    /// Georgia with a `COLR` glyph for 'A' that draws an 'A' in red over a
+    /// 'B' in the text colour.
+    fn layered_font() -> Vec<u8> {
+        let data = std::fs::read("assets/Georgia.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let [a, b] = ['A', 'B'].map(|c| face.glyph_index(c).unwrap().0);
+        let mut colr = Vec::new();
+        for value in [0u16, 1, 0, 14, 0, 20, 2] {
+            colr.extend(value.to_be_bytes());
+        }
+        for value in [a, 0, 2, b, 0xFFFF, a, 0] {
+            colr.extend(value.to_be_bytes());
+        }
+        let mut cpal = Vec::new();
+        for value in [0u16, 1, 1, 1, 0, 14, 0] {
+            cpal.extend(value.to_be_bytes());
+        }
+        // blue, green, red, alpha
+        cpal.extend([0, 0, 255, 255]);
+
+        let raw = face.raw_face();
+        let mut tables: BTreeMap<[u8; 4], Vec<u8>> = raw
+            .table_records
+            .into_iter()
+            .map(|record| {
+                let start = record.offset as usize;
+                let data = &raw.data[start..start + record.length as usize];
+                (record.tag.to_bytes(), data.to_vec())
+            })
+            .collect();
+        tables.insert(*b"COLR", colr);
+        tables.insert(*b"CPAL", cpal);
+        subset::write_font(tables)
+    }
+
+    fn operators(stream: &Stream) -> Vec<String> {
+        let content = Content::decode(&stream.content).unwrap();
+        content.operations.into_iter().map(|o| o.operator).collect()
+    }
+
+    #[test]
+    fn layers_are_filled_from_the_palette() {
+        let data = layered_font();
+        let face = Face::parse(&data, 0).unwrap();
+        let a = face.glyph_index('A').unwrap().0;
+        assert!(has_colour_glyphs(&face));
+        assert!(is_colour_glyph(&face, a));
+
+        let mut doc = Document::with_version("1.5");
+        let form = glyph_form(&mut doc, &face, a).unwrap().unwrap();
+        let operators = operators(&form);
+        // the 'B' is filled in the text colour, then the 'A' in red
+        let first_fill = operators.iter().position(|o| o == "f").unwrap();
+        assert_eq!(operators[0], "m");
+        assert_eq!(&operators[first_fill + 1..first_fill + 3], ["q", "rg"]);
+        assert_eq!(operators.last().unwrap(), "Q");
+        assert!(form.dict.get(b"Resources").is_err());
+    }
+
+    #[test]
+    fn outlines_without_colour_use_the_fill_colour() {
+        let data = layered_font();
+        let face = Face::parse(&data, 0).unwrap();
+        let b = face.glyph_index('B').unwrap().0;
+        assert!(!is_colour_glyph(&face, b));
+
+        let form = glyph_form(&mut Document::with_version("1.5"), &face, b)
+            .unwrap()
+            .unwrap();
+        let operators = operators(&form);
+        assert!(!operators.contains(&"rg".to_owned()));
+        assert_eq!(operators.last().unwrap(), "f");
+    }
+
+    #[test]
+    fn bitmaps_become_images_with_soft_masks() {
+        let data = std::fs::read("assets/NotoColorEmoji-Partial.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let copyright = face.glyph_index('©').unwrap().0;
+        assert!(is_colour_glyph(&face, copyright));
+
+        let mut doc = Document::with_version("1.5");
+        let form = glyph_form(&mut doc, &face, copyright).unwrap().unwrap();
+        assert_eq!(operators(&form), ["q", "cm", "Do", "Q"]);
+        let resources = form.dict.get(b"Resources").unwrap().as_dict().unwrap();
+        let images = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        let image_id = images.get(b"Im").unwrap().as_reference().unwrap();
+        let image = doc.get_object(image_id).unwrap().as_stream().unwrap();
+        assert_eq!(image.dict.get(b"Width").unwrap().as_i64().unwrap(), 136);
+        assert_eq!(image.dict.get(b"Height").unwrap().as_i64().unwrap(), 128);
+        let mask_id = image.dict.get(b"SMask").unwrap().as_reference().unwrap();
+        let mask = doc.get_object(mask_id).unwrap().as_stream().unwrap();
+        assert_eq!(
+            mask.dict.get(b"ColorSpace").unwrap().as_name_str().unwrap(),
+            "DeviceGray"
+        );
+        assert_eq!(mask.content.len(), 136 * 128);
+    }
+}
@@ -0,0 +1,215 @@
+//! Single byte encodings for simple fonts
+//!
+//! Simple fonts (`Type1`, `TrueType` and `Type3`) can only address 256 glyphs
+//! with single byte codes. See section 9.6.5 of the PDF spec.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use lopdf::{dictionary, Object};
+
+/// Characters for the codes `0x80` to `0x9F` in `WinAnsiEncoding`.
+///
+/// The rest of the encoding matches ISO 8859-1. See Annex D of the PDF spec.
+const WIN_ANSI_80_TO_9F: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
+
+/// A character that can not be encoded in a font's encoding, either because
+/// the encoding has no code for it or because all 255 codes of a simple font
+/// are already used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodingError {
+    pub font: String,
+    pub character: char,
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} (U+{:04X}) can not be encoded in the font {}",
+            self.character, self.character as u32, self.font
+        )
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+/// Get the character for a code in `WinAnsiEncoding`.
+pub(crate) fn win_ansi_char(code: u8) -> Option<char> {
+    match code {
+        0x20..=0x7E | 0xA0..=0xFF => Some(code as char),
+        0x80..=0x9F => WIN_ANSI_80_TO_9F[(code - 0x80) as usize],
+        _ => None,
+    }
+}
+
+/// Get the `WinAnsiEncoding` code for a character.
+pub(crate) fn win_ansi_code(c: char) -> Option<u8> {
+    match c as u32 {
+        0x20..=0x7E | 0xA0..=0xFF => Some(c as u8),
+        _ => WIN_ANSI_80_TO_9F
+            .iter()
+            .position(|&x| x == Some(c))
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+/// Codes that are not used by `WinAnsiEncoding` and can be assigned to other characters.
+fn free_codes() -> impl Iterator<Item = u8> {
+    (0x01..=0xFF).filter(|&code| win_ansi_char(code).is_none())
+}
+
+/// Get the glyph name for a character.
+///
+/// Uses the `uniXXXX` form which viewers map back to Unicode.
+pub(crate) fn glyph_name(c: char) -> String {
+    if (c as u32) <= 0xFFFF {
+        format!("uni{:04X}", c as u32)
+    } else {
+        format!("u{:X}", c as u32)
+    }
+}
+
+/// A `WinAnsiEncoding` based encoding that grows as text is encoded.
+///
+/// Characters outside of `WinAnsiEncoding` are assigned to unused codes and
+/// written as a `Differences` array.
+#[derive(Default)]
+pub(crate) struct SimpleEncoding {
+    used: BTreeMap<u8, char>,
+    differences: BTreeMap<char, u8>,
+}
+
+impl SimpleEncoding {
+    /// Encode a character, assigning it a code if needed.
+    ///
+    /// Returns `None` if there are no codes left.
+    pub fn encode(&mut self, c: char) -> Option<u8> {
+        let code = match win_ansi_code(c).or_else(|| self.differences.get(&c).copied()) {
+            Some(code) => code,
+            None => {
+                let code = free_codes().find(|code| !self.used.contains_key(code))?;
+                self.differences.insert(c, code);
+                code
+            }
+        };
+        self.used.insert(code, c);
+        Some(code)
+    }
+
+    /// The codes that have been used, mapped to their characters.
+    pub fn used(&self) -> &BTreeMap<u8, char> {
+        &self.used
+    }
+
+    /// Create the value of a font's `Encoding` entry.
+    pub fn as_object(&self) -> Object {
+        if self.differences.is_empty() {
+            return "WinAnsiEncoding".into();
+        }
+        let differences: BTreeMap<u8, String> = self
+            .differences
+            .iter()
+            .map(|(&c, &code)| (code, glyph_name(c)))
+            .collect();
+
+        Object::Dictionary(dictionary! {
+            "Type" => "Encoding",
+            "BaseEncoding" => "WinAnsiEncoding",
+            "Differences" => differences_array(&differences),
+        })
+    }
+}
+
+/// Create a `Differences` array naming the glyph for each code.
+///
+/// Consecutive codes share a single starting code. See section 9.6.5.1 of
+/// the PDF spec.
+pub(crate) fn differences_array(names: &BTreeMap<u8, String>) -> Vec<Object> {
+    let mut array = Vec::new();
+    let mut next_code = None;
+    for (&code, name) in names {
+        if next_code != Some(code) {
+            array.push(Object::Integer(code as i64));
+        }
+        array.push(Object::Name(name.as_bytes().to_vec()));
+        next_code = code.checked_add(1);
+    }
+    array
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn win_ansi_round_trip() {
+        for code in 0..=255u8 {
+            if let Some(c) = win_ansi_char(code) {
+                assert_eq!(win_ansi_code(c), Some(code));
+            }
+        }
+        assert_eq!(win_ansi_code('é'), Some(0xE9));
+        assert_eq!(win_ansi_code('–'), Some(0x96));
+        assert_eq!(win_ansi_code('→'), None);
+    }
+
+    #[test]
+    fn encoding_assigns_differences() {
+        let mut encoding = SimpleEncoding::default();
+        assert_eq!(encoding.encode('a'), Some(b'a'));
+        assert_eq!(encoding.encode('→'), Some(1));
+        assert_eq!(encoding.encode('←'), Some(2));
+        assert_eq!(encoding.encode('→'), Some(1));
+
+        let Object::Dictionary(dict) = encoding.as_object() else {
+            panic!("expected an encoding dictionary");
+        };
+        let differences = dict.get(b"Differences").unwrap().as_array().unwrap();
+        assert_eq!(differences.len(), 3);
+        assert_eq!(differences[0].as_i64().unwrap(), 1);
+        assert_eq!(differences[1].as_name_str().unwrap(), "uni2192");
+        assert_eq!(differences[2].as_name_str().unwrap(), "uni2190");
+    }
+
+    #[test]
+    fn encoding_runs_out_of_codes() {
+        let mut encoding = SimpleEncoding::default();
+        let assigned = ('\u{2200}'..'\u{2300}')
+            .take_while(|&c| encoding.encode(c).is_some())
+            .count();
+        assert_eq!(assigned, 37);
+    }
+}
@@ -0,0 +1,473 @@
+//! Font collections and variable font instances
+//!
+//! A face in a TrueType collection (`.ttc`) can not be embedded directly, so
+//! its tables are copied out into a standalone font. Variable fonts are
+//! embedded as a static instance by outlining every glyph at the chosen axis
+//! coordinates and writing the outlines as a new `glyf` table. This works for
+//! both `gvar` and `CFF2` variable fonts, cubic curves are approximated with
+//! quadratic ones. Hinting is dropped as it would not match the new outlines.
+use std::collections::BTreeMap;
+
+use owned_ttf_parser::{fonts_in_collection, name_id, Face, GlyphId, OutlineBuilder, Tag};
+
+use super::names::{name_string, sanitise};
+use super::subset::{raw_table, read_u16, read_u32, write_font};
+
+/// Tables that are copied unchanged into an instance.
+const COPIED_TABLES: [&[u8; 4]; 10] = [
+    b"cmap", b"gasp", b"GDEF", b"GPOS", b"GSUB", b"kern", b"OS/2", b"post", b"vhea", b"vmtx",
+];
+
+/// Find the index of the face in a collection with a PostScript or full name.
+pub(crate) fn find_face(data: &[u8], name: &str) -> Option<u32> {
+    (0..fonts_in_collection(data).unwrap_or(1)).find(|&index| {
+        Face::parse(data, index).is_ok_and(|face| {
+            [name_id::POST_SCRIPT_NAME, name_id::FULL_NAME]
+                .into_iter()
+                .any(|id| name_string(&face, id).as_deref() == Some(name))
+        })
+    })
+}
+
+/// Copy a face out of a collection into a standalone font.
+///
+/// Faces with CFF outlines, such as those in a Noto CJK `.otc`, are written
+/// as OpenType (`OTTO`) fonts, see [write_font].
+pub(crate) fn extract(face: &Face) -> Vec<u8> {
+    let raw = face.raw_face();
+    let tables = raw
+        .table_records
+        .into_iter()
+        .map(|record| {
+            let start = record.offset as usize;
+            let data = &raw.data[start..start + record.length as usize];
+            (record.tag.to_bytes(), data.to_vec())
+        })
+        .collect();
+    write_font(tables)
+}
+
+/// A named instance from the `fvar` table.
+pub(crate) struct NamedInstance {
+    pub subfamily: String,
+    pub postscript_name: Option<String>,
+    pub coordinates: Vec<(Tag, f32)>,
+}
+
+/// Read the named instances of a variable font.
+///
+/// See the `fvar` table in the OpenType spec.
+pub(crate) fn named_instances(face: &Face) -> Vec<NamedInstance> {
+    let Some(fvar) = raw_table(face, b"fvar") else {
+        return vec![];
+    };
+    let fixed = |offset: usize| read_u32(fvar, offset) as i32 as f32 / 65536.;
+    let axes_offset = read_u16(fvar, 4) as usize;
+    let axis_count = read_u16(fvar, 8) as usize;
+    let axis_size = read_u16(fvar, 10) as usize;
+    let instance_count = read_u16(fvar, 12) as usize;
+    let instance_size = read_u16(fvar, 14) as usize;
+
+    let tags: Vec<Tag> = (0..axis_count)
+        .map(|i| Tag(read_u32(fvar, axes_offset + i * axis_size)))
+        .collect();
+    let instances_offset = axes_offset + axis_count * axis_size;
+    (0..instance_count)
+        .map(|i| instances_offset + i * instance_size)
+        .filter(|offset| offset + instance_size <= fvar.len())
+        .map(|offset| NamedInstance {
+            subfamily: name_string(face, read_u16(fvar, offset)).unwrap_or_default(),
+            postscript_name: if instance_size >= axis_count * 4 + 6 {
+                name_string(face, read_u16(fvar, offset + 4 + axis_count * 4))
+            } else {
+                None
+            },
+            coordinates: tags
+                .iter()
+                .enumerate()
+                .map(|(axis, &tag)| (tag, fixed(offset + 4 + axis * 4)))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Create a PostScript name for an instance.
+///
+/// Named instances use their own PostScript name or the subfamily name,
+/// other instances list their coordinates, following Adobe Technical Note
+/// #5902.
+pub(crate) fn instance_name(
+    face: &Face,
+    instance: Option<&NamedInstance>,
+    coordinates: &[(Tag, f32)],
+) -> String {
+    let prefix = name_string(face, 25)
+        .or_else(|| name_string(face, name_id::TYPOGRAPHIC_FAMILY))
+        .or_else(|| name_string(face, name_id::FAMILY))
+        .map(|name| sanitise(&name))
+        .unwrap_or_else(|| String::from("Unnamed"));
+    let name = match instance {
+        Some(NamedInstance {
+            postscript_name: Some(name),
+            ..
+        }) => name.clone(),
+        Some(instance) => format!("{}-{}", prefix, instance.subfamily),
+        None => coordinates.iter().fold(prefix, |name, (tag, value)| {
+            format!("{}_{}{}", name, value, tag)
+        }),
+    };
+    sanitise(&name)
+}
+
+/// Write a static font from a face with variation coordinates already set.
+///
+/// The coordinates are needed to set the weight class. Outlines and advance
+/// widths (including `HVAR` deltas) are read at the coordinates, but `hhea`,
+/// `OS/2` and `GPOS` are copied from the default instance without applying
+/// `MVAR` or the deltas in `GPOS`, so line metrics and kerning are those of
+/// the default instance.
+pub(crate) fn instance(face: &Face, coordinates: &[(Tag, f32)], postscript_name: &str) -> Vec<u8> {
+    let mut tables = BTreeMap::new();
+    for tag in COPIED_TABLES {
+        if let Some(data) = raw_table(face, tag) {
+            tables.insert(*tag, data.to_vec());
+        }
+    }
+
+    let glyphs: Vec<Glyph> = (0..face.number_of_glyphs())
+        .map(|glyph_id| {
+            let mut glyph = Glyph::default();
+            face.outline_glyph(GlyphId(glyph_id), &mut glyph);
+            glyph.finish_contour();
+            glyph
+        })
+        .collect();
+
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    let mut hmtx = Vec::new();
+    let mut bbox = [i16::MAX, i16::MAX, i16::MIN, i16::MIN];
+    let mut max_advance = 0;
+    for (glyph_id, glyph) in glyphs.iter().enumerate() {
+        loca.extend((glyf.len() as u32).to_be_bytes());
+        let glyph_bbox = glyph.write(&mut glyf);
+        let advance = face
+            .glyph_hor_advance(GlyphId(glyph_id as u16))
+            .unwrap_or(0);
+        max_advance = max_advance.max(advance);
+        hmtx.extend(advance.to_be_bytes());
+        hmtx.extend(glyph_bbox.map_or(0, |b| b[0]).to_be_bytes());
+        if let Some(glyph_bbox) = glyph_bbox {
+            bbox = [
+                bbox[0].min(glyph_bbox[0]),
+                bbox[1].min(glyph_bbox[1]),
+                bbox[2].max(glyph_bbox[2]),
+                bbox[3].max(glyph_bbox[3]),
+            ];
+        }
+    }
+    loca.extend((glyf.len() as u32).to_be_bytes());
+    tables.insert(*b"glyf", glyf);
+    tables.insert(*b"loca", loca);
+    tables.insert(*b"hmtx", hmtx);
+
+    let mut hhea = raw_table(face, b"hhea").expect("no hhea found").to_vec();
+    hhea[10..12].copy_from_slice(&max_advance.to_be_bytes());
+    hhea[34..36].copy_from_slice(&face.number_of_glyphs().to_be_bytes());
+    tables.insert(*b"hhea", hhea);
+
+    let mut head = raw_table(face, b"head").expect("no head found").to_vec();
+    // checkSumAdjustment is set once the font is written
+    head[8..12].copy_from_slice(&[0; 4]);
+    if bbox[0] <= bbox[2] {
+        for (i, value) in bbox.iter().enumerate() {
+            head[36 + i * 2..38 + i * 2].copy_from_slice(&value.to_be_bytes());
+        }
+    }
+    // always write a long `loca`
+    head[50..52].copy_from_slice(&1u16.to_be_bytes());
+    tables.insert(*b"head", head);
+
+    tables.insert(*b"maxp", build_maxp(face.number_of_glyphs(), &glyphs));
+    tables.insert(*b"name", build_name(face, postscript_name));
+
+    let weight = coordinates
+        .iter()
+        .find(|(tag, _)| *tag == Tag::from_bytes(b"wght"))
+        .map(|(_, value)| value.round().clamp(1., 1000.) as u16);
+    if let (Some(os2), Some(weight)) = (tables.get_mut(b"OS/2"), weight) {
+        os2[4..6].copy_from_slice(&weight.to_be_bytes());
+    }
+
+    write_font(tables)
+}
+
+/// Build a version 1.0 `maxp` table, without any hinting limits.
+fn build_maxp(num_glyphs: u16, glyphs: &[Glyph]) -> Vec<u8> {
+    let max_points = glyphs.iter().map(Glyph::point_count).max().unwrap_or(0);
+    let max_contours = glyphs.iter().map(|g| g.contours.len()).max().unwrap_or(0);
+    let mut maxp = Vec::new();
+    maxp.extend(0x0001_0000u32.to_be_bytes());
+    maxp.extend(num_glyphs.to_be_bytes());
+    maxp.extend((max_points as u16).to_be_bytes());
+    maxp.extend((max_contours as u16).to_be_bytes());
+    // composite points and contours, zones, and then hinting limits
+    maxp.extend([0, 0, 0, 0, 0, 2]);
+    maxp.resize(32, 0);
+    maxp
+}
+
+/// Build a `name` table with the family, subfamily, full and PostScript names.
+fn build_name(face: &Face, postscript_name: &str) -> Vec<u8> {
+    let family = name_string(face, name_id::FAMILY).unwrap_or_else(|| postscript_name.to_owned());
+    let subfamily = postscript_name
+        .split_once(['-', '_'])
+        .map_or("Regular", |(_, subfamily)| subfamily)
+        .to_owned();
+    let names = [
+        (name_id::FAMILY, family.clone()),
+        (name_id::SUBFAMILY, subfamily.clone()),
+        (name_id::FULL_NAME, format!("{} {}", family, subfamily)),
+        (name_id::POST_SCRIPT_NAME, postscript_name.to_owned()),
+    ];
+
+    let mut records = Vec::new();
+    let mut strings = Vec::new();
+    for (id, name) in names.iter().cloned() {
+        let encoded: Vec<u8> = name.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        // Windows platform, Unicode BMP encoding, US English
+        for value in [3, 1, 0x409, id, encoded.len() as u16, strings.len() as u16] {
+            records.extend(value.to_be_bytes());
+        }
+        strings.extend(encoded);
+    }
+    let mut name = Vec::new();
+    for value in [0, names.len() as u16, 6 + records.len() as u16] {
+        name.extend(value.to_be_bytes());
+    }
+    name.extend(records);
+    name.extend(strings);
+    name
+}
+
+#[derive(Clone, Copy)]
+struct Point {
+    x: f32,
+    y: f32,
+    on_curve: bool,
+}
+
+/// Collects a glyph outline as TrueType contours.
+#[derive(Default)]
+struct Glyph {
+    contours: Vec<Vec<Point>>,
+    current: Vec<Point>,
+}
+
+impl Glyph {
+    fn point(&mut self, x: f32, y: f32, on_curve: bool) {
+        self.current.push(Point { x, y, on_curve });
+    }
+
+    fn last(&self) -> Point {
+        *self.current.last().expect("outline segment without a move")
+    }
+
+    fn finish_contour(&mut self) {
+        let mut contour = std::mem::take(&mut self.current);
+        // contours are implicitly closed, so drop a final point on the start
+        if contour.len() > 1 {
+            let (first, last) = (contour[0], contour[contour.len() - 1]);
+            if last.on_curve && (first.x, first.y) == (last.x, last.y) {
+                contour.pop();
+            }
+        }
+        if contour.len() > 1 {
+            self.contours.push(contour);
+        }
+    }
+
+    fn point_count(&self) -> usize {
+        self.contours.iter().map(Vec::len).sum()
+    }
+
+    /// Write the glyph as a simple glyph and return its bounding box.
+    ///
+    /// Every coordinate is written as a 16 bit delta for simplicity.
+    fn write(&self, glyf: &mut Vec<u8>) -> Option<[i16; 4]> {
+        let points: Vec<(i16, i16, bool)> = self
+            .contours
+            .iter()
+            .flatten()
+            .map(|p| (p.x.round() as i16, p.y.round() as i16, p.on_curve))
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        let bbox = points
+            .iter()
+            .fold([i16::MAX, i16::MAX, i16::MIN, i16::MIN], |b, &(x, y, _)| {
+                [b[0].min(x), b[1].min(y), b[2].max(x), b[3].max(y)]
+            });
+
+        glyf.extend((self.contours.len() as i16).to_be_bytes());
+        for value in bbox {
+            glyf.extend(value.to_be_bytes());
+        }
+        let mut end = 0;
+        for contour in self.contours.iter() {
+            end += contour.len();
+            glyf.extend(((end - 1) as u16).to_be_bytes());
+        }
+        // no instructions
+        glyf.extend(0u16.to_be_bytes());
+        glyf.extend(points.iter().map(|&(_, _, on_curve)| on_curve as u8));
+        let mut previous = 0;
+        for &(x, _, _) in points.iter() {
+            glyf.extend(x.wrapping_sub(previous).to_be_bytes());
+            previous = x;
+        }
+        let mut previous = 0;
+        for &(_, y, _) in points.iter() {
+            glyf.extend(y.wrapping_sub(previous).to_be_bytes());
+            previous = y;
+        }
+        if glyf.len() % 2 == 1 {
+            glyf.push(0);
+        }
+        Some(bbox)
+    }
+}
+
+impl OutlineBuilder for Glyph {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.finish_contour();
+        self.point(x, y, true);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.point(x, y, true);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.point(x1, y1, false);
+        self.point(x, y, true);
+    }
+
+    /// Approximate a cubic curve with four quadratic curves.
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let start = self.last();
+        let cubic = [(start.x, start.y), (x1, y1), (x2, y2), (x, y)];
+        for half in split_cubic(cubic) {
+            for [p0, c1, c2, p3] in split_cubic(half) {
+                let control = (
+                    (3. * (c1.0 + c2.0) - p0.0 - p3.0) / 4.,
+                    (3. * (c1.1 + c2.1) - p0.1 - p3.1) / 4.,
+                );
+                self.quad_to(control.0, control.1, p3.0, p3.1);
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        self.finish_contour();
+    }
+}
+
+type Cubic = [(f32, f32); 4];
+
+/// Split a cubic curve in half with de Casteljau's algorithm.
+fn split_cubic(c: Cubic) -> [Cubic; 2] {
+    let mid = |a: (f32, f32), b: (f32, f32)| ((a.0 + b.0) / 2., (a.1 + b.1) / 2.);
+    let ab = mid(c[0], c[1]);
+    let bc = mid(c[1], c[2]);
+    let cd = mid(c[2], c[3]);
+    let abc = mid(ab, bc);
+    let bcd = mid(bc, cd);
+    let centre = mid(abc, bcd);
+    [[c[0], ab, abc, centre], [centre, bcd, cd, c[3]]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Combine fonts into a collection, moving their table offsets.
+    fn collection(fonts: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"ttcf".to_vec();
+        data.extend(0x0001_0000u32.to_be_bytes());
+        data.extend((fonts.len() as u32).to_be_bytes());
+        let mut offset = 12 + 4 * fonts.len();
+        for font in fonts {
+            data.extend((offset as u32).to_be_bytes());
+            offset += font.len();
+        }
+        for font in fonts {
+            let base = data.len() as u32;
+            let mut font = font.clone();
+            for i in 0..read_u16(&font, 4) as usize {
+                let record = 12 + 16 * i + 8;
+                let moved = read_u32(&font, record) + base;
+                font[record..record + 4].copy_from_slice(&moved.to_be_bytes());
+            }
+            data.extend(font);
+        }
+        data
+    }
+
+    #[test]
+    fn extract_faces_from_collection() {
+        let data = collection(&[
+            std::fs::read("assets/Georgia.ttf").unwrap(),
+            std::fs::read("assets/FiraCodeNerdFontMono-Medium.ttf").unwrap(),
+            std::fs::read("assets/Cantarell-VF.otf").unwrap(),
+        ]);
+        assert_eq!(fonts_in_collection(&data), Some(3));
+        let face = Face::parse(&data, 1).unwrap();
+        let name = name_string(&face, name_id::POST_SCRIPT_NAME).unwrap();
+        assert_eq!(find_face(&data, &name), Some(1));
+        assert_eq!(find_face(&data, "Georgia"), Some(0));
+        assert_eq!(find_face(&data, "Helvetica"), None);
+
+        let extracted = extract(&face);
+        assert_eq!(extracted[0..4], 0x0001_0000u32.to_be_bytes());
+        let standalone = Face::parse(&extracted, 0).unwrap();
+        assert_eq!(standalone.number_of_glyphs(), face.number_of_glyphs());
+        assert_eq!(
+            name_string(&standalone, name_id::POST_SCRIPT_NAME).unwrap(),
+            name
+        );
+
+        // faces with CFF outlines keep the OpenType sfnt version
+        let cff = Face::parse(&data, 2).unwrap();
+        let extracted = extract(&cff);
+        assert_eq!(&extracted[0..4], b"OTTO");
+        assert!(Face::parse(&extracted, 0).unwrap().tables().cff2.is_some());
+    }
+
+    #[test]
+    fn instance_variable_font() {
+        let data = std::fs::read("assets/Cantarell-VF.otf").unwrap();
+        let mut face = Face::parse(&data, 0).unwrap();
+        let bold = named_instances(&face)
+            .into_iter()
+            .find(|instance| instance.subfamily == "Bold")
+            .unwrap();
+        let light_advance = face.glyph_hor_advance(face.glyph_index('a').unwrap());
+        for &(tag, value) in bold.coordinates.iter() {
+            face.set_variation(tag, value).unwrap();
+        }
+        let name = instance_name(&face, Some(&bold), &bold.coordinates);
+        let font = instance(&face, &bold.coordinates, &name);
+
+        let static_face = Face::parse(&font, 0).unwrap();
+        assert!(!static_face.is_variable());
+        assert!(static_face.tables().glyf.is_some());
+        assert_eq!(static_face.number_of_glyphs(), face.number_of_glyphs());
+        assert_eq!(static_face.weight().to_number(), 700);
+        let a = static_face.glyph_index('a').unwrap();
+        assert_ne!(static_face.glyph_hor_advance(a), light_advance);
+        assert!(static_face.glyph_bounding_box(a).is_some());
+        assert_eq!(super::super::names::postscript_name(&static_face), name);
+    }
+}
@@ -0,0 +1,175 @@
+//! Font descriptor metrics
+//!
+//! Reads the `head`, `hhea`, `OS/2` and `post` tables to fill in a font
+//! descriptor. All values are scaled to glyph space, which has 1000 units per
+//! em. See section 9.8 of the PDF spec.
+use lopdf::{dictionary, Dictionary, Object};
+use owned_ttf_parser::{Face, Tag};
+
+// Font descriptor flags, see section 9.8.2 of the PDF spec.
+const FIXED_PITCH: u32 = 1 << 0;
+const SERIF: u32 = 1 << 1;
+const SYMBOLIC: u32 = 1 << 2;
+const NONSYMBOLIC: u32 = 1 << 5;
+const ITALIC: u32 = 1 << 6;
+
+pub(crate) struct FontMetrics {
+    pub bbox: [i64; 4],
+    pub ascent: i64,
+    pub descent: i64,
+    pub cap_height: i64,
+    pub x_height: i64,
+    pub italic_angle: f32,
+    pub stem_v: i64,
+    pub flags: u32,
+}
+
+impl FontMetrics {
+    pub fn from_face(face: &Face) -> Self {
+        let scale = |v: i16| (v as f32 * 1000.0 / face.units_per_em() as f32).round() as i64;
+        let glyph_top = |c: char| {
+            face.glyph_index(c)
+                .and_then(|gid| face.glyph_bounding_box(gid))
+                .map(|bbox| bbox.y_max)
+        };
+
+        let bbox = face.global_bounding_box();
+        let cap_height = face
+            .capital_height()
+            .filter(|&h| h > 0)
+            .or_else(|| glyph_top('H'))
+            .unwrap_or_else(|| face.ascender());
+        let x_height = face
+            .x_height()
+            .filter(|&h| h > 0)
+            .or_else(|| glyph_top('x'))
+            .unwrap_or(cap_height / 3 * 2);
+        let italic_angle = face.italic_angle().unwrap_or(0.);
+
+        let mut flags = if is_symbolic(face) {
+            SYMBOLIC
+        } else {
+            NONSYMBOLIC
+        };
+        if face.is_monospaced() {
+            flags |= FIXED_PITCH;
+        }
+        if is_serif(face) {
+            flags |= SERIF;
+        }
+        if italic_angle != 0. || face.is_italic() {
+            flags |= ITALIC;
+        }
+
+        Self {
+            bbox: [
+                scale(bbox.x_min),
+                scale(bbox.y_min),
+                scale(bbox.x_max),
+                scale(bbox.y_max),
+            ],
+            ascent: scale(face.ascender()),
+            descent: scale(face.descender()),
+            cap_height: scale(cap_height),
+            x_height: scale(x_height),
+            italic_angle,
+            stem_v: stem_v(face.weight().to_number()),
+            flags,
+        }
+    }
+
+    /// Mark the font as nonsymbolic.
+    ///
+    /// Simple fonts that are given a standard encoding must be nonsymbolic for
+    /// the encoding to be used.
+    pub fn nonsymbolic(mut self) -> Self {
+        self.flags = (self.flags & !SYMBOLIC) | NONSYMBOLIC;
+        self
+    }
+
+    /// Create a font descriptor dictionary, without a font file.
+    pub fn descriptor(&self, font_name: &str) -> Dictionary {
+        dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => font_name,
+            "Flags" => self.flags as i64,
+            "FontBBox" => self.bbox.iter().map(|&v| Object::Integer(v)).collect::<Vec<_>>(),
+            "ItalicAngle" => self.italic_angle,
+            "Ascent" => self.ascent,
+            "Descent" => self.descent,
+            "CapHeight" => self.cap_height,
+            "XHeight" => self.x_height,
+            "StemV" => self.stem_v,
+        }
+    }
+}
+
+/// Estimate the dominant vertical stem width from the weight class.
+///
+/// TrueType fonts do not record stem widths so this uses the same
+/// approximation as most PDF generators.
+fn stem_v(weight: u16) -> i64 {
+    50 + (weight as i64 / 65).pow(2)
+}
+
+/// A font is symbolic if it has no Unicode `cmap` or does not cover the basic
+/// Latin letters.
+fn is_symbolic(face: &Face) -> bool {
+    let has_unicode_cmap = face
+        .tables()
+        .cmap
+        .map(|cmap| cmap.subtables.into_iter().any(|s| s.is_unicode()))
+        .unwrap_or(false);
+    !has_unicode_cmap
+        || !('A'..='Z')
+            .chain('a'..='z')
+            .all(|c| face.glyph_index(c).is_some())
+}
+
+/// Detect serif fonts from the `OS/2` table.
+///
+/// Uses the PANOSE serif style for Latin text fonts, falling back to the IBM
+/// font family class.
+fn is_serif(face: &Face) -> bool {
+    let Some(os2) = face.raw_face().table(Tag::from_bytes(b"OS/2")) else {
+        return false;
+    };
+    if os2.len() < 34 {
+        return false;
+    }
+    let family_class = os2[30];
+    let panose_family = os2[32];
+    let panose_serif_style = os2[33];
+    if panose_family == 2 && panose_serif_style > 1 {
+        (2..=10).contains(&panose_serif_style)
+    } else {
+        matches!(family_class, 1..=5 | 7)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn georgia_metrics() {
+        let data = std::fs::read("assets/Georgia.ttf").unwrap();
+        let metrics = FontMetrics::from_face(&Face::parse(&data, 0).unwrap());
+
+        assert_eq!(metrics.flags, SERIF | NONSYMBOLIC);
+        assert!(metrics.bbox[0] < 0 && metrics.bbox[1] < 0);
+        assert!(metrics.bbox[2] > 1000 && metrics.bbox[3] > metrics.cap_height);
+        assert!(metrics.x_height < metrics.cap_height);
+        assert!(metrics.cap_height < metrics.ascent);
+        assert!(metrics.descent < 0);
+    }
+
+    #[test]
+    fn fira_code_metrics() {
+        let data = std::fs::read("assets/FiraCodeNerdFontMono-Medium.ttf").unwrap();
+        let metrics = FontMetrics::from_face(&Face::parse(&data, 0).unwrap());
+
+        assert_eq!(metrics.flags & (FIXED_PITCH | SERIF), FIXED_PITCH);
+        assert_eq!(metrics.italic_angle, 0.);
+    }
+}
@@ -0,0 +1,1553 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::iter::once;
+
+use lopdf::content::{Content, Operation};
+use lopdf::StringFormat;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+use owned_ttf_parser::{fonts_in_collection, AsFaceRef, Face, GlyphId, OwnedFace, Tag};
+
+mod colour;
+mod encoding;
+mod instance;
+mod metrics;
+mod names;
+mod shaping;
+mod standard;
+mod subset;
+mod to_unicode;
+mod vertical;
+
+use crate::document::{ContentBuilder, Resources};
+pub use encoding::EncodingError;
+use encoding::SimpleEncoding;
+use metrics::FontMetrics;
+use standard::StandardFont;
+
+// TODO @robyoung refactor this to make the usage clearer.
+pub trait FontReference {
+    fn object_id(&self) -> ObjectId;
+    fn render_text(&self, text: &str) -> Vec<Object>;
+
+    /// Like [FontReference::render_text], but returns an error for text the
+    /// font can not encode instead of panicking.
+    fn try_render_text(&self, text: &str) -> Result<Vec<Object>, EncodingError> {
+        Ok(self.render_text(text))
+    }
+
+    /// Create the operations to show a string of text.
+    fn render_operations(&self, text: &str) -> Vec<Operation> {
+        vec![Operation::new("Tj", self.render_text(text))]
+    }
+
+    /// Like [FontReference::render_operations], but returns an error for
+    /// text the font can not encode instead of panicking.
+    fn try_render_operations(&self, text: &str) -> Result<Vec<Operation>, EncodingError> {
+        Ok(vec![Operation::new("Tj", self.try_render_text(text)?)])
+    }
+
+    /// Write any font objects that depend on the text rendered with this font.
+    ///
+    /// Must be called once all content using the font has been rendered.
+    /// Returns warnings about anything that will not look as expected, for
+    /// the caller to report.
+    fn finish(&self, _doc: &mut Document) -> Vec<String> {
+        vec![]
+    }
+
+    /// Whether the font has a glyph for a character.
+    fn covers(&self, c: char) -> bool;
+
+    /// The width of a string of text at a font size, in text space units.
+    fn text_width(&self, text: &str, size: f32) -> f32;
+
+    /// The distance from the baseline to the top of the font at a font size.
+    fn ascent(&self, size: f32) -> f32;
+
+    /// The distance from the baseline to the bottom of the font at a font size.
+    ///
+    /// This is negative for fonts that extend below the baseline.
+    fn descent(&self, size: f32) -> f32;
+
+    /// Whether text is written top to bottom, with the `Identity-V` encoding.
+    fn is_vertical(&self) -> bool {
+        false
+    }
+
+    /// The height of a string of text written vertically at a font size.
+    ///
+    /// Defaults to one em per character, the default vertical displacement
+    /// of a CIDFont.
+    fn text_height(&self, text: &str, size: f32) -> f32 {
+        text.chars().count() as f32 * size
+    }
+}
+
+/// Scale a value in font units to text space at a font size.
+fn scale_to_size(value: f32, units_per_em: u16, size: f32) -> f32 {
+    value * size / units_per_em as f32
+}
+
+/// The horizontal advance of a glyph from `hmtx`, in font units.
+fn glyph_advance(face: &Face, glyph_id: u16) -> i32 {
+    face.glyph_hor_advance(GlyphId(glyph_id)).unwrap_or(0) as i32
+}
+
+/// A reference to one of the standard 14 fonts.
+///
+/// Text is transcoded to `WinAnsiEncoding`, or the built in encoding of the
+/// symbolic fonts.
+#[derive(Clone)]
+pub struct InternalFontReference {
+    object_id: ObjectId,
+    metrics: &'static StandardFont,
+}
+
+impl Default for InternalFontReference {
+    fn default() -> Self {
+        Self {
+            object_id: ObjectId::default(),
+            metrics: standard::standard_font("Helvetica").unwrap(),
+        }
+    }
+}
+
+impl InternalFontReference {
+    /// Transcode text into the encoding of the font.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        text.chars()
+            .map(|character| {
+                self.metrics.encode(character).ok_or_else(|| EncodingError {
+                    font: self.metrics.name.to_owned(),
+                    character,
+                })
+            })
+            .collect()
+    }
+}
+
+impl FontReference for InternalFontReference {
+    fn object_id(&self) -> ObjectId {
+        self.object_id
+    }
+
+    /// Panics if the text contains characters that the font can not encode.
+    fn render_text(&self, text: &str) -> Vec<Object> {
+        self.try_render_text(text)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_render_text(&self, text: &str) -> Result<Vec<Object>, EncodingError> {
+        Ok(vec![Object::String(
+            self.encode(text)?,
+            StringFormat::Literal,
+        )])
+    }
+
+    fn covers(&self, c: char) -> bool {
+        self.metrics.encode(c).is_some()
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let metrics = self.metrics;
+        let width: u32 = text
+            .chars()
+            .filter_map(|ch| metrics.encode(ch))
+            .map(|code| metrics.width(code) as u32)
+            .sum();
+        scale_to_size(width as f32, 1000, size)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        scale_to_size(self.metrics.ascent as f32, 1000, size)
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        scale_to_size(self.metrics.descent as f32, 1000, size)
+    }
+}
+
+pub struct ExternalFontReference {
+    object_id: ObjectId,
+    face: OwnedFace,
+    subset: bool,
+    shaping: bool,
+    vertical: bool,
+    /// Warnings from loading the font, returned by [FontReference::finish].
+    warnings: Vec<String>,
+    /// The glyphs that were shown, mapped to the text they represent.
+    used_glyphs: RefCell<BTreeMap<u16, String>>,
+    /// Characters that were shown with `.notdef` as the font has no glyph.
+    missing: RefCell<BTreeSet<char>>,
+}
+
+impl ExternalFontReference {
+    fn new(
+        object_id: ObjectId,
+        face: OwnedFace,
+        subset: bool,
+        shaping: bool,
+        vertical: bool,
+        warnings: Vec<String>,
+    ) -> Self {
+        Self {
+            object_id,
+            face,
+            subset,
+            shaping,
+            vertical,
+            warnings,
+            used_glyphs: RefCell::new(BTreeMap::new()),
+            missing: RefCell::new(BTreeSet::new()),
+        }
+    }
+}
+
+impl FontReference for ExternalFontReference {
+    fn object_id(&self) -> ObjectId {
+        self.object_id
+    }
+
+    /// Characters missing from the font are shown with the `.notdef` glyph.
+    fn render_text(&self, text: &str) -> Vec<Object> {
+        let face = self.face.as_face_ref();
+        let mut used_glyphs = self.used_glyphs.borrow_mut();
+        let mut missing = self.missing.borrow_mut();
+        vec![Object::String(
+            text.chars()
+                .map(|ch| match face.glyph_index(ch) {
+                    Some(gid) => {
+                        used_glyphs.entry(gid.0).or_insert_with(|| ch.to_string());
+                        gid.0
+                    }
+                    None => {
+                        missing.insert(ch);
+                        0
+                    }
+                })
+                .flat_map(|x| vec![(x >> 8) as u8, (x & 255) as u8])
+                .collect::<Vec<u8>>(),
+            StringFormat::Hexadecimal,
+        )]
+    }
+
+    /// Characters missing from the font are shown with `.notdef`, so this
+    /// never fails.
+    fn try_render_operations(&self, text: &str) -> Result<Vec<Operation>, EncodingError> {
+        Ok(self.render_operations(text))
+    }
+
+    /// Show text with `Tj`, or with `TJ` if shaping is enabled.
+    ///
+    /// Shaped text is wrapped in an `ActualText` span so that ligatures still
+    /// extract as the original characters. See section 14.9.4 of the PDF spec.
+    /// Vertical text is shaped for its vertical forms and shown with `Tj`.
+    fn render_operations(&self, text: &str) -> Vec<Operation> {
+        if !self.shaping {
+            return vec![Operation::new("Tj", self.render_text(text))];
+        }
+        let face = self.face.as_face_ref();
+        let glyphs = if self.vertical {
+            shaping::shape_vertical(face, text)
+        } else {
+            shaping::shape(face, text)
+        };
+        let mut used_glyphs = self.used_glyphs.borrow_mut();
+        for (glyph, cluster_text) in glyphs.iter().zip(shaping::cluster_texts(text, &glyphs)) {
+            if glyph.glyph_id == 0 {
+                self.missing.borrow_mut().extend(cluster_text.chars());
+                continue;
+            }
+            used_glyphs
+                .entry(glyph.glyph_id)
+                .or_insert_with(|| cluster_text.to_owned());
+        }
+
+        vec![
+            Operation::new(
+                "BDC",
+                vec![
+                    "Span".into(),
+                    dictionary! { "ActualText" => shaping::text_string(text) }.into(),
+                ],
+            ),
+            if self.vertical {
+                Operation::new(
+                    "Tj",
+                    vec![Object::String(
+                        glyphs
+                            .iter()
+                            .flat_map(|g| g.glyph_id.to_be_bytes())
+                            .collect(),
+                        StringFormat::Hexadecimal,
+                    )],
+                )
+            } else {
+                Operation::new(
+                    "TJ",
+                    vec![Object::Array(shaping::text_items(face, &glyphs))],
+                )
+            },
+            Operation::new("EMC", vec![]),
+        ]
+    }
+
+    fn covers(&self, c: char) -> bool {
+        self.face.as_face_ref().glyph_index(c).is_some()
+    }
+
+    /// Measure with the advances from `hmtx`, adjusted by shaping if it is
+    /// enabled.
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        let width = if self.shaping {
+            shaping::shape(face, text)
+                .iter()
+                .map(|glyph| glyph_advance(face, glyph.glyph_id) + glyph.advance_adjustment)
+                .sum()
+        } else {
+            text.chars()
+                .map(|ch| face.glyph_index(ch).map_or(0, |gid| gid.0))
+                .map(|gid| glyph_advance(face, gid))
+                .sum::<i32>()
+        };
+        scale_to_size(width as f32, face.units_per_em(), size)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.ascender() as f32, face.units_per_em(), size)
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.descender() as f32, face.units_per_em(), size)
+    }
+
+    fn is_vertical(&self) -> bool {
+        self.vertical
+    }
+
+    /// Measure with the advances from `vmtx`, after substituting vertical
+    /// forms if shaping is enabled.
+    fn text_height(&self, text: &str, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        let height = if self.shaping {
+            shaping::shape_vertical(face, text)
+                .iter()
+                .map(|glyph| vertical::advance(face, glyph.glyph_id))
+                .sum()
+        } else {
+            text.chars()
+                .map(|ch| face.glyph_index(ch).map_or(0, |gid| gid.0))
+                .map(|gid| vertical::advance(face, gid))
+                .sum::<i32>()
+        };
+        scale_to_size(height as f32, face.units_per_em(), size)
+    }
+
+    /// Warns about characters that were shown with `.notdef`.
+    fn finish(&self, doc: &mut Document) -> Vec<String> {
+        let face = self.face.as_face_ref();
+        let used_glyphs = self.used_glyphs.borrow();
+
+        let (font_data, font_name, written_glyphs) = if self.subset {
+            let glyphs = used_glyphs
+                .keys()
+                .copied()
+                .chain([0])
+                .collect::<BTreeSet<_>>();
+            (
+                subset::subset(face, &glyphs),
+                names::font_name(face, Some(&subset::subset_tag(used_glyphs.keys()))),
+                glyphs,
+            )
+        } else {
+            (
+                self.face.as_slice().to_vec(),
+                names::font_name(face, None),
+                (0..face.number_of_glyphs()).collect(),
+            )
+        };
+        let width_list = create_width_list(face, written_glyphs.iter().copied());
+
+        // add font stream, CFF outlines are embedded as a whole OpenType font
+        // see section 9.9 of the PDF spec
+        let cff = has_cff_outlines(face);
+        let (stream_dict, font_file, cid_font_type) = if cff {
+            (
+                dictionary! { "Subtype" => "OpenType" },
+                "FontFile3",
+                "CIDFontType0",
+            )
+        } else {
+            (
+                dictionary! { "Length1" => font_data.len() as u32 },
+                "FontFile2",
+                "CIDFontType2",
+            )
+        };
+        let stream_id = doc.add_object(Stream::new(stream_dict, font_data).with_compression(true));
+
+        // add font descriptor object
+        let mut descriptor = FontMetrics::from_face(face).descriptor(&font_name);
+        descriptor.set(font_file, stream_id);
+        let descriptor_id = doc.add_object(descriptor);
+        // add descendant font object
+        let mut descendant_font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => cid_font_type,
+            "BaseFont" => font_name.as_str(),
+            "CIDSystemInfo" => dictionary! {
+                "Registry" => Object::String("Adobe".into(), StringFormat::Literal),
+                "Ordering" => Object::String("Identity".into(), StringFormat::Literal),
+                "Supplement" => 0,
+            },
+            "FontDescriptor" => descriptor_id,
+            "W" => width_list,
+            "DW" => 1000,
+        };
+        let encoding = if self.vertical {
+            let (default, metrics) = vertical::create_metrics(face, written_glyphs);
+            descendant_font.set("DW2", default);
+            descendant_font.set("W2", metrics);
+            "Identity-V"
+        } else {
+            "Identity-H"
+        };
+        let descendant_font_id = doc.add_object(descendant_font);
+        let to_unicode_id = create_to_unicode(doc, &used_glyphs, &font_name);
+
+        // add font object
+        doc.objects.insert(
+            self.object_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type0",
+                "BaseFont" => format!("{}-{}", font_name, encoding),
+                "Encoding" => encoding,
+                "DescendantFonts" => vec![descendant_font_id.into()],
+                "ToUnicode" => to_unicode_id,
+            }),
+        );
+
+        let mut warnings = self.warnings.clone();
+        let missing = self.missing.borrow();
+        if !missing.is_empty() {
+            warnings.push(format!(
+                "{}: no glyph for {:?}, shown as .notdef",
+                names::postscript_name(face),
+                missing.iter().collect::<String>()
+            ));
+        }
+        warnings
+    }
+}
+
+pub fn type0(font: &[u8]) -> FontType0Builder {
+    FontType0Builder::new(font)
+}
+
+pub struct FontType0Builder {
+    font_data: Vec<u8>,
+    subset: bool,
+    shaping: bool,
+    vertical: bool,
+    face: FaceSelector,
+    named_instance: Option<String>,
+    variations: Vec<(Tag, f32)>,
+}
+
+/// Which face to use from a font collection.
+enum FaceSelector {
+    Index(u32),
+    Name(String),
+}
+
+impl FontType0Builder {
+    pub fn new(font: &[u8]) -> Self {
+        Self {
+            font_data: font.to_vec(),
+            subset: false,
+            shaping: false,
+            vertical: false,
+            face: FaceSelector::Index(0),
+            named_instance: None,
+            variations: vec![],
+        }
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(&std::fs::read(path)?))
+    }
+
+    /// Only embed the glyphs that are rendered with this font.
+    pub fn subset(mut self, subset: bool) -> Self {
+        self.subset = subset;
+        self
+    }
+
+    /// Shape text with kerning and ligatures in [FontReference::render_operations].
+    pub fn shaping(mut self, shaping: bool) -> Self {
+        self.shaping = shaping;
+        self
+    }
+
+    /// Write text top to bottom with the `Identity-V` encoding.
+    ///
+    /// Glyphs are positioned with the vertical metrics from `vmtx`, see
+    /// section 9.7.4.3 of the PDF spec.
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    /// Select a face in a font collection (`.ttc`) by index.
+    pub fn face_index(mut self, index: u32) -> Self {
+        self.face = FaceSelector::Index(index);
+        self
+    }
+
+    /// Select a face in a font collection (`.ttc`) by PostScript or full name.
+    pub fn face_name(mut self, name: &str) -> Self {
+        self.face = FaceSelector::Name(name.to_owned());
+        self
+    }
+
+    /// Use a named instance of a variable font, by subfamily or PostScript name.
+    pub fn named_instance(mut self, name: &str) -> Self {
+        self.named_instance = Some(name.to_owned());
+        self
+    }
+
+    /// Set an axis of a variable font, such as `wght` or `wdth`.
+    ///
+    /// Overrides the coordinate from a named instance.
+    pub fn variation(mut self, axis: &str, value: f32) -> Self {
+        let tag: [u8; 4] = axis
+            .as_bytes()
+            .try_into()
+            .unwrap_or_else(|_| panic!("Variation axis {:?} is not a four letter tag", axis));
+        self.variations.push((Tag::from_bytes(&tag), value));
+        self
+    }
+
+    /// Parse the selected face, as a standalone static font.
+    ///
+    /// Faces from collections are copied out and variable fonts are instanced,
+    /// see [instance].
+    fn load_face(self) -> OwnedFace {
+        let index = match &self.face {
+            FaceSelector::Index(index) => *index,
+            FaceSelector::Name(name) => instance::find_face(&self.font_data, name)
+                .unwrap_or_else(|| panic!("No face named {} in font", name)),
+        };
+        let face = Face::parse(&self.font_data, index).expect("could not parse font data");
+
+        let named = self.named_instance.as_ref().map(|name| {
+            instance::named_instances(&face)
+                .into_iter()
+                .find(|i| &i.subfamily == name || i.postscript_name.as_ref() == Some(name))
+                .unwrap_or_else(|| panic!("No named instance {} in font", name))
+        });
+        let mut coordinates = named
+            .as_ref()
+            .map_or_else(Vec::new, |named| named.coordinates.clone());
+        for &(tag, value) in self.variations.iter() {
+            match coordinates.iter_mut().find(|(t, _)| *t == tag) {
+                Some(coordinate) => coordinate.1 = value,
+                None => coordinates.push((tag, value)),
+            }
+        }
+
+        let data = if !coordinates.is_empty() {
+            let mut face = face;
+            for &(tag, value) in coordinates.iter() {
+                face.set_variation(tag, value)
+                    .unwrap_or_else(|| panic!("Font has no variation axis {}", tag));
+            }
+            let name = instance::instance_name(&face, named.as_ref(), &coordinates);
+            instance::instance(&face, &coordinates, &name)
+        } else if fonts_in_collection(&self.font_data).is_some() {
+            instance::extract(&face)
+        } else {
+            self.font_data
+        };
+        OwnedFace::from_vec(data, 0).expect("could not parse font data")
+    }
+
+    /// Reserve the font object in the document.
+    ///
+    /// The font itself is written by [FontReference::finish] so that it can be
+    /// subset to the glyphs that were actually used.
+    ///
+    /// Fonts with CFF outlines can not be subset yet so are embedded whole.
+    ///
+    /// Anything that will not look as expected, such as colour glyphs drawn in
+    /// one colour, is returned as a warning by [FontReference::finish].
+    pub fn add_to_doc(self, doc: &mut Document) -> ExternalFontReference {
+        let (shaping, mut subset, vertical) = (self.shaping, self.subset, self.vertical);
+        let face = self.load_face();
+        let name = names::postscript_name(face.as_face_ref());
+        let mut warnings = Vec::new();
+        if subset && has_cff_outlines(face.as_face_ref()) {
+            warnings.push(format!(
+                "{}: subsetting CFF fonts is not supported, embedding the whole font",
+                name
+            ));
+            subset = false;
+        }
+        if colour::has_colour_glyphs(face.as_face_ref()) {
+            warnings.push(format!(
+                "{}: colour glyphs are drawn in one colour, use fonts::colour to draw them in colour",
+                name
+            ));
+        }
+        if vertical && face.as_face_ref().tables().vmtx.is_none() {
+            warnings.push(format!(
+                "{}: font has no vertical metrics, using its ascender and descender",
+                name
+            ));
+        }
+        ExternalFontReference::new(
+            doc.new_object_id(),
+            face,
+            subset,
+            shaping,
+            vertical,
+            warnings,
+        )
+    }
+}
+
+/// Whether the font has `CFF` or `CFF2` outlines rather than `glyf` outlines.
+fn has_cff_outlines(face: &Face) -> bool {
+    let tables = face.tables();
+    tables.cff.is_some() || tables.cff2.is_some()
+}
+
+/// Write a `ToUnicode` CMap for the glyphs that were shown with a font.
+fn create_to_unicode(
+    doc: &mut Document,
+    glyphs: &BTreeMap<u16, String>,
+    face_name: &str,
+) -> ObjectId {
+    let cmap = to_unicode::create_cmap(face_name, 2, glyphs);
+    doc.add_object(Stream::new(dictionary! {}, cmap.into_bytes()).with_compression(true))
+}
+
+fn create_width_list<I: IntoIterator<Item = u16>>(face: &Face, glyph_ids: I) -> Vec<Object> {
+    let mut widths = Vec::new();
+    let mut current_low_gid = 0;
+    let mut current_high_gid = 0;
+    let mut current_widths = Vec::new();
+    let scaling_factor = 1000.0 / face.units_per_em() as f32;
+
+    for gid in glyph_ids {
+        if let Some(width) = face.glyph_hor_advance(GlyphId(gid)) {
+            let width = (width as f32 * scaling_factor) as i64;
+            if gid == current_high_gid {
+                current_widths.push(Object::Integer(width));
+                current_high_gid += 1;
+            } else {
+                if !current_widths.is_empty() {
+                    widths.push(Object::Integer(current_low_gid as i64));
+                    widths.push(Object::Array(std::mem::take(&mut current_widths)));
+                }
+                current_widths.push(Object::Integer(width));
+                current_low_gid = gid;
+                current_high_gid = gid + 1;
+            }
+        }
+    }
+
+    if !current_widths.is_empty() {
+        widths.push(Object::Integer(current_low_gid as i64));
+        widths.push(Object::Array(current_widths));
+    }
+
+    widths
+}
+
+pub fn type1(base_font: &str) -> FontType1Builder {
+    FontType1Builder {
+        base_font: base_font.to_owned(),
+    }
+}
+
+#[derive(Default)]
+pub struct FontType1Builder {
+    base_font: String,
+}
+
+impl FontType1Builder {
+    /// Add the font to the document.
+    ///
+    /// Only the standard 14 fonts are supported as the font is not embedded.
+    pub fn add_to_doc(self, doc: &mut Document) -> InternalFontReference {
+        let metrics = standard::standard_font(&self.base_font)
+            .unwrap_or_else(|| panic!("{} is not one of the standard 14 fonts", self.base_font));
+        let mut font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => self.base_font,
+        };
+        if !metrics.is_symbolic() {
+            font.set("Encoding", "WinAnsiEncoding");
+        }
+
+        InternalFontReference {
+            object_id: doc.add_object(font),
+            metrics,
+        }
+    }
+}
+
+pub fn true_type(font: &[u8]) -> FontTrueTypeBuilder {
+    FontTrueTypeBuilder {
+        font_data: font.to_vec(),
+        subset: false,
+    }
+}
+
+pub struct FontTrueTypeBuilder {
+    font_data: Vec<u8>,
+    subset: bool,
+}
+
+impl FontTrueTypeBuilder {
+    /// Only embed the glyphs that are rendered with this font.
+    pub fn subset(mut self, subset: bool) -> Self {
+        self.subset = subset;
+        self
+    }
+
+    /// Reserve the font object in the document.
+    ///
+    /// The font itself is written by [FontReference::finish] once the
+    /// encoding is known.
+    ///
+    /// Panics for fonts with CFF outlines, use [FontType0Builder] for those.
+    pub fn add_to_doc(self, doc: &mut Document) -> TrueTypeFontReference {
+        let face = OwnedFace::from_vec(self.font_data, 0).expect("could not parse font data");
+        if has_cff_outlines(face.as_face_ref()) {
+            panic!("TrueType fonts must have glyf outlines, use a Type0 font for CFF outlines");
+        }
+        TrueTypeFontReference {
+            object_id: doc.new_object_id(),
+            face,
+            subset: self.subset,
+            encoding: RefCell::new(SimpleEncoding::default()),
+        }
+    }
+}
+
+/// A reference to a TrueType font used as a simple font.
+///
+/// Text is encoded with single byte codes, see [SimpleEncoding].
+pub struct TrueTypeFontReference {
+    object_id: ObjectId,
+    face: OwnedFace,
+    subset: bool,
+    encoding: RefCell<SimpleEncoding>,
+}
+
+impl FontReference for TrueTypeFontReference {
+    fn object_id(&self) -> ObjectId {
+        self.object_id
+    }
+
+    /// Panics once all 255 single byte codes are used, see
+    /// [FontReference::try_render_text].
+    fn render_text(&self, text: &str) -> Vec<Object> {
+        self.try_render_text(text)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Characters are given codes as they are first shown, returning an error
+    /// once there are none left. Use a Type0 font for text with many
+    /// different characters.
+    fn try_render_text(&self, text: &str) -> Result<Vec<Object>, EncodingError> {
+        let mut encoding = self.encoding.borrow_mut();
+        let codes = text
+            .chars()
+            .map(|character| {
+                encoding.encode(character).ok_or_else(|| EncodingError {
+                    font: names::postscript_name(self.face.as_face_ref()),
+                    character,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(vec![Object::String(codes, StringFormat::Literal)])
+    }
+
+    fn finish(&self, doc: &mut Document) -> Vec<String> {
+        let face = self.face.as_face_ref();
+        let encoding = self.encoding.borrow();
+        let used = encoding.used();
+        let first_char = used.keys().next().copied().unwrap_or(b' ');
+        let last_char = used.keys().last().copied().unwrap_or(b' ');
+        let scaling_factor = 1000.0 / face.units_per_em() as f32;
+        let widths: Vec<Object> = (first_char..=last_char)
+            .map(|code| {
+                used.get(&code)
+                    .and_then(|&ch| face.glyph_index(ch))
+                    .and_then(|gid| face.glyph_hor_advance(gid))
+                    .map(|width| (width as f32 * scaling_factor) as i64)
+                    .unwrap_or(0)
+                    .into()
+            })
+            .collect();
+
+        let (font_data, font_name) = if self.subset {
+            let glyphs: BTreeSet<u16> = used
+                .values()
+                .filter_map(|&ch| face.glyph_index(ch).map(|gid| gid.0))
+                .collect();
+            (
+                subset::subset(face, &glyphs),
+                names::font_name(face, Some(&subset::subset_tag(glyphs.iter()))),
+            )
+        } else {
+            (self.face.as_slice().to_vec(), names::font_name(face, None))
+        };
+
+        // add font stream
+        let stream_id = doc.add_object(Stream::new(
+            dictionary! {
+                "Length1" => font_data.len() as u32,
+            },
+            font_data,
+        ));
+
+        // add font descriptor object
+        let mut descriptor = FontMetrics::from_face(face)
+            .nonsymbolic()
+            .descriptor(&font_name);
+        descriptor.set("MissingWidth", 0);
+        descriptor.set("FontFile2", stream_id);
+        let descriptor_id = doc.add_object(descriptor);
+        // add font object
+        doc.objects.insert(
+            self.object_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "TrueType",
+                "BaseFont" => font_name.as_str(),
+                "FirstChar" => first_char,
+                "LastChar" => last_char,
+                "Widths" => widths,
+                "Encoding" => encoding.as_object(),
+                "FontDescriptor" => descriptor_id,
+            }),
+        );
+        vec![]
+    }
+    fn covers(&self, c: char) -> bool {
+        self.face.as_face_ref().glyph_index(c).is_some()
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        let width: i32 = text
+            .chars()
+            .filter_map(|ch| face.glyph_index(ch))
+            .map(|gid| glyph_advance(face, gid.0))
+            .sum();
+        scale_to_size(width as f32, face.units_per_em(), size)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.ascender() as f32, face.units_per_em(), size)
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.descender() as f32, face.units_per_em(), size)
+    }
+}
+
+pub(crate) fn type3() -> FontType3Builder {
+    FontType3Builder::default()
+}
+
+/// A font whose glyphs are drawn with content stream operators.
+///
+/// Glyphs are drawn in a glyph space of `units_per_em` units with the origin
+/// on the baseline. They are painted in the current fill colour unless the
+/// font is coloured. See section 9.6.5 of the PDF spec.
+pub(crate) struct FontType3Builder {
+    name: String,
+    units_per_em: u16,
+    bbox: [i32; 4],
+    coloured: bool,
+    encoding: SimpleEncoding,
+    glyphs: BTreeMap<char, (f32, Vec<Operation>)>,
+}
+
+impl Default for FontType3Builder {
+    fn default() -> Self {
+        Self {
+            name: "Type3".to_owned(),
+            units_per_em: 1000,
+            bbox: [0, 0, 1000, 1000],
+            coloured: false,
+            encoding: SimpleEncoding::default(),
+            glyphs: BTreeMap::new(),
+        }
+    }
+}
+
+impl FontType3Builder {
+    /// Set the name used for the `ToUnicode` CMap.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Set the size of the glyph space, defaults to 1000 units per em.
+    pub fn units_per_em(mut self, units_per_em: u16) -> Self {
+        self.units_per_em = units_per_em;
+        self
+    }
+
+    /// Set a box, in glyph space, that encloses every glyph.
+    ///
+    /// Defaults to one em square sitting on the baseline.
+    pub fn bbox(mut self, llx: i32, lly: i32, urx: i32, ury: i32) -> Self {
+        self.bbox = [llx, lly, urx, ury];
+        self
+    }
+
+    /// Allow glyphs to set their own colours (`d0`) instead of being painted in
+    /// the current fill colour (`d1`).
+    pub fn coloured(mut self, coloured: bool) -> Self {
+        self.coloured = coloured;
+        self
+    }
+
+    /// Add a glyph for a character, drawn by a closure in glyph space.
+    ///
+    /// Glyphs can not use fonts or images as the glyph procedures have no
+    /// resources of their own.
+    pub(crate) fn glyph<F>(mut self, c: char, width: f32, draw: F) -> Self
+    where
+        F: FnOnce(ContentBuilder) -> ContentBuilder,
+    {
+        if self.encoding.encode(c).is_none() {
+            panic!("No single byte code left for {:?} in Type3 font", c);
+        }
+        let resources = Resources::default();
+        let operations = draw(ContentBuilder::new(&resources)).build_operations();
+        self.glyphs.insert(c, (width, operations));
+        self
+    }
+
+    /// Add the font, its glyph procedures and `ToUnicode` CMap to the document.
+    pub fn add_to_doc(self, doc: &mut Document) -> Type3FontReference {
+        let codes = self.encoding.used().clone();
+        let first_char = codes.keys().next().copied().unwrap_or(0);
+        let last_char = codes.keys().last().copied().unwrap_or(0);
+
+        let mut char_procs = Dictionary::new();
+        for (&c, (width, operations)) in self.glyphs.iter() {
+            let metrics = if self.coloured {
+                Operation::new("d0", vec![(*width).into(), 0.into()])
+            } else {
+                let mut operands = vec![(*width).into(), 0.into()];
+                operands.extend(self.bbox.iter().map(|&v| v.into()));
+                Operation::new("d1", operands)
+            };
+            let content = Content {
+                operations: once(metrics)
+                    .chain(operations.iter().cloned())
+                    .collect::<Vec<_>>(),
+            };
+            let stream = Stream::new(dictionary! {}, content.encode().unwrap());
+            char_procs.set(encoding::glyph_name(c), doc.add_object(stream));
+        }
+        let widths: Vec<Object> = (first_char..=last_char)
+            .map(|code| codes.get(&code).map_or(0., |c| self.glyphs[c].0).into())
+            .collect();
+
+        let to_unicode: BTreeMap<u16, String> = codes
+            .iter()
+            .map(|(&code, c)| (code as u16, c.to_string()))
+            .collect();
+        let cmap = to_unicode::create_cmap(&self.name, 1, &to_unicode);
+        let to_unicode_id = doc.add_object(Stream::new(dictionary! {}, cmap.into_bytes()));
+
+        let scale = 1. / self.units_per_em as f32;
+        let object_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type3",
+            "FontBBox" => self.bbox.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+            "FontMatrix" => vec![scale.into(), 0.into(), 0.into(), scale.into(), 0.into(), 0.into()],
+            "CharProcs" => char_procs,
+            "Encoding" => dictionary! {
+                "Type" => "Encoding",
+                "Differences" => encoding::differences_array(
+                    &codes.iter().map(|(&code, &c)| (code, encoding::glyph_name(c))).collect(),
+                ),
+            },
+            "FirstChar" => first_char,
+            "LastChar" => last_char,
+            "Widths" => widths,
+            "Resources" => dictionary! {},
+            "ToUnicode" => to_unicode_id,
+        });
+
+        Type3FontReference {
+            object_id,
+            units_per_em: self.units_per_em,
+            bbox: self.bbox,
+            codes: codes.iter().map(|(&code, &c)| (c, code)).collect(),
+            widths: self
+                .glyphs
+                .iter()
+                .map(|(&c, (width, _))| (c, *width))
+                .collect(),
+        }
+    }
+}
+
+pub struct Type3FontReference {
+    object_id: ObjectId,
+    units_per_em: u16,
+    bbox: [i32; 4],
+    codes: BTreeMap<char, u8>,
+    widths: BTreeMap<char, f32>,
+}
+
+impl FontReference for Type3FontReference {
+    fn object_id(&self) -> ObjectId {
+        self.object_id
+    }
+
+    fn render_text(&self, text: &str) -> Vec<Object> {
+        vec![Object::String(
+            text.chars()
+                .map(|c| {
+                    *self
+                        .codes
+                        .get(&c)
+                        .unwrap_or_else(|| panic!("No glyph for {:?} in Type3 font", c))
+                })
+                .collect(),
+            StringFormat::Hexadecimal,
+        )]
+    }
+
+    fn covers(&self, c: char) -> bool {
+        self.codes.contains_key(&c)
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let width: f32 = text.chars().filter_map(|c| self.widths.get(&c)).sum();
+        scale_to_size(width, self.units_per_em, size)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        scale_to_size(self.bbox[3] as f32, self.units_per_em, size)
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        scale_to_size(self.bbox[1] as f32, self.units_per_em, size)
+    }
+}
+
+pub fn colour(font: &[u8]) -> FontColourBuilder {
+    FontColourBuilder::new(font)
+}
+
+/// A font with colour glyphs, such as emoji.
+///
+/// Each glyph is drawn into a Form XObject by [colour::glyph_form] and painted
+/// by a glyph procedure of a Type3 font, so colour glyphs sit inline with the
+/// text around them. Only the glyphs that were shown are written.
+pub struct FontColourBuilder {
+    font_data: Vec<u8>,
+    face_index: u32,
+}
+
+impl FontColourBuilder {
+    pub fn new(font: &[u8]) -> Self {
+        Self {
+            font_data: font.to_vec(),
+            face_index: 0,
+        }
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        Ok(Self::new(&std::fs::read(path)?))
+    }
+
+    /// Select a face in a font collection (`.ttc`) by index.
+    pub fn face_index(mut self, index: u32) -> Self {
+        self.face_index = index;
+        self
+    }
+
+    /// Reserve the font object in the document.
+    ///
+    /// The font is written by [FontReference::finish] once the glyphs that
+    /// were shown are known.
+    pub fn add_to_doc(self, doc: &mut Document) -> ColourFontReference {
+        let face = OwnedFace::from_vec(self.font_data, self.face_index)
+            .expect("could not parse font data");
+        if !colour::has_colour_glyphs(face.as_face_ref()) {
+            panic!("Font has no COLR, sbix or CBDT table");
+        }
+        ColourFontReference {
+            object_id: doc.new_object_id(),
+            face,
+            used_glyphs: RefCell::new(BTreeMap::new()),
+        }
+    }
+}
+
+pub struct ColourFontReference {
+    object_id: ObjectId,
+    face: OwnedFace,
+    /// The glyphs that were shown, mapped to their code and the text they
+    /// represent.
+    used_glyphs: RefCell<BTreeMap<u16, (u8, String)>>,
+}
+
+impl FontReference for ColourFontReference {
+    fn object_id(&self) -> ObjectId {
+        self.object_id
+    }
+
+    /// Panics once 255 different glyphs have been shown, see
+    /// [FontReference::try_render_text].
+    fn render_text(&self, text: &str) -> Vec<Object> {
+        self.try_render_text(text)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Text is always shaped so that emoji sequences, such as flags and
+    /// skin tones, become a single glyph. Codes are given out in the order the
+    /// glyphs are first shown, returning an error once there are none left.
+    /// No codes are given out for text that returns an error.
+    fn try_render_text(&self, text: &str) -> Result<Vec<Object>, EncodingError> {
+        let glyphs = shaping::shape(self.face.as_face_ref(), text);
+        let mut used_glyphs = self.used_glyphs.borrow_mut();
+        let mut new_glyphs: Vec<(u16, &str)> = Vec::new();
+        for (glyph, cluster_text) in glyphs.iter().zip(shaping::cluster_texts(text, &glyphs)) {
+            if !used_glyphs.contains_key(&glyph.glyph_id)
+                && !new_glyphs.iter().any(|&(id, _)| id == glyph.glyph_id)
+            {
+                new_glyphs.push((glyph.glyph_id, cluster_text));
+            }
+        }
+        let free_codes = u8::MAX as usize - used_glyphs.len();
+        if let Some((_, cluster_text)) = new_glyphs.get(free_codes) {
+            return Err(EncodingError {
+                font: names::postscript_name(self.face.as_face_ref()),
+                character: cluster_text.chars().next().unwrap_or_default(),
+            });
+        }
+        for (glyph_id, cluster_text) in new_glyphs {
+            let code = used_glyphs.len() as u8 + 1;
+            used_glyphs.insert(glyph_id, (code, cluster_text.to_owned()));
+        }
+        let codes = glyphs
+            .iter()
+            .map(|glyph| used_glyphs[&glyph.glyph_id].0)
+            .collect();
+        Ok(vec![Object::String(codes, StringFormat::Hexadecimal)])
+    }
+
+    /// Only colour glyphs are covered, along with the joiners and selectors
+    /// that combine them, so that a font stack can fall back to the colour
+    /// font just for emoji.
+    fn covers(&self, c: char) -> bool {
+        let face = self.face.as_face_ref();
+        face.glyph_index(c)
+            .is_some_and(|gid| colour::is_colour_glyph(face, gid.0))
+            || matches!(c, '\u{200D}' | '\u{20E3}' | '\u{FE00}'..='\u{FE0F}')
+    }
+
+    fn text_width(&self, text: &str, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        let width: i32 = shaping::shape(face, text)
+            .iter()
+            .map(|glyph| glyph_advance(face, glyph.glyph_id) + glyph.advance_adjustment)
+            .sum();
+        scale_to_size(width as f32, face.units_per_em(), size)
+    }
+
+    fn ascent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.ascender() as f32, face.units_per_em(), size)
+    }
+
+    fn descent(&self, size: f32) -> f32 {
+        let face = self.face.as_face_ref();
+        scale_to_size(face.descender() as f32, face.units_per_em(), size)
+    }
+
+    /// Write the Type3 font, with a glyph procedure that paints the Form
+    /// XObject of each glyph that was shown. See section 9.6.5 of the PDF spec.
+    fn finish(&self, doc: &mut Document) -> Vec<String> {
+        let face = self.face.as_face_ref();
+        let used_glyphs = self.used_glyphs.borrow();
+        let first_char = used_glyphs
+            .values()
+            .map(|(code, _)| *code)
+            .min()
+            .unwrap_or(1);
+        let last_char = used_glyphs
+            .values()
+            .map(|(code, _)| *code)
+            .max()
+            .unwrap_or(1);
+
+        let mut char_procs = Dictionary::new();
+        let mut forms = Dictionary::new();
+        let mut names = BTreeMap::new();
+        let mut widths = vec![Object::Integer(0); (last_char - first_char) as usize + 1];
+        let mut warnings = Vec::new();
+        for (&gid, &(code, _)) in used_glyphs.iter() {
+            let name = format!("g{}", gid);
+            let advance = glyph_advance(face, gid);
+            let mut operations = vec![Operation::new("d0", vec![advance.into(), 0.into()])];
+            match colour::glyph_form(doc, face, gid) {
+                Ok(Some(form)) => {
+                    forms.set(name.as_str(), doc.add_object(form));
+                    operations.push(Operation::new(
+                        "Do",
+                        vec![Object::Name(name.clone().into_bytes())],
+                    ));
+                }
+                Ok(None) => {}
+                Err(warning) => {
+                    warnings.push(format!("{}: {}", names::postscript_name(face), warning))
+                }
+            }
+            let content = Content { operations };
+            let stream = Stream::new(dictionary! {}, content.encode().unwrap());
+            char_procs.set(name.as_str(), doc.add_object(stream));
+            widths[(code - first_char) as usize] = advance.into();
+            names.insert(code, name);
+        }
+
+        let font_name = names::font_name(face, None);
+        let to_unicode: BTreeMap<u16, String> = used_glyphs
+            .values()
+            .map(|(code, text)| (*code as u16, text.clone()))
+            .collect();
+        let cmap = to_unicode::create_cmap(&font_name, 1, &to_unicode);
+        let to_unicode_id =
+            doc.add_object(Stream::new(dictionary! {}, cmap.into_bytes()).with_compression(true));
+
+        let bbox = face.global_bounding_box();
+        let scale = 1. / face.units_per_em() as f32;
+        doc.objects.insert(
+            self.object_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type3",
+                "FontBBox" => [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max]
+                    .iter()
+                    .map(|&v| (v as i64).into())
+                    .collect::<Vec<Object>>(),
+                "FontMatrix" => vec![scale.into(), 0.into(), 0.into(), scale.into(), 0.into(), 0.into()],
+                "CharProcs" => char_procs,
+                "Encoding" => dictionary! {
+                    "Type" => "Encoding",
+                    "Differences" => encoding::differences_array(&names),
+                },
+                "FirstChar" => first_char,
+                "LastChar" => last_char,
+                "Widths" => widths,
+                "Resources" => dictionary! { "XObject" => forms },
+                "ToUnicode" => to_unicode_id,
+            }),
+        );
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type1_transcodes_text() {
+        let mut doc = Document::with_version("1.5");
+        let helvetica = type1("Helvetica").add_to_doc(&mut doc);
+        assert_eq!(
+            helvetica.encode("café – 1€").unwrap(),
+            b"caf\xE9 \x96 1\x80"
+        );
+        let font = doc.get_dictionary(helvetica.object_id()).unwrap();
+        assert_eq!(
+            font.get(b"Encoding").unwrap().as_name_str().unwrap(),
+            "WinAnsiEncoding"
+        );
+
+        let err = helvetica.encode("αβ").unwrap_err();
+        assert_eq!(err.character, 'α');
+        assert_eq!(
+            err.to_string(),
+            "'α' (U+03B1) can not be encoded in the font Helvetica"
+        );
+
+        let symbol = type1("Symbol").add_to_doc(&mut doc);
+        assert_eq!(symbol.encode("αβ").unwrap(), b"ab");
+        let font = doc.get_dictionary(symbol.object_id()).unwrap();
+        assert!(font.get(b"Encoding").is_err());
+    }
+
+    #[test]
+    fn type0_embeds_cff_as_open_type() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Cantarell-VF.otf")
+            .unwrap()
+            .subset(true)
+            .add_to_doc(&mut doc);
+        font.render_text("Hello");
+        font.finish(&mut doc);
+
+        let type0 = doc.get_dictionary(font.object_id()).unwrap();
+        let descendant = type0.get(b"DescendantFonts").unwrap().as_array().unwrap()[0]
+            .as_reference()
+            .unwrap();
+        let descendant = doc.get_dictionary(descendant).unwrap();
+        assert_eq!(
+            descendant.get(b"Subtype").unwrap().as_name_str().unwrap(),
+            "CIDFontType0"
+        );
+        let descriptor = descendant
+            .get(b"FontDescriptor")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let descriptor = doc.get_dictionary(descriptor).unwrap();
+        assert!(descriptor.get(b"FontFile2").is_err());
+        let font_file = descriptor
+            .get(b"FontFile3")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let font_file = doc.get_object(font_file).unwrap().as_stream().unwrap();
+        assert_eq!(
+            font_file
+                .dict
+                .get(b"Subtype")
+                .unwrap()
+                .as_name_str()
+                .unwrap(),
+            "OpenType"
+        );
+    }
+
+    #[test]
+    fn type0_embeds_variable_font_instance() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Cantarell-VF.otf")
+            .unwrap()
+            .named_instance("Bold")
+            .subset(true)
+            .add_to_doc(&mut doc);
+        font.render_text("Hello");
+        font.finish(&mut doc);
+
+        let type0 = doc.get_dictionary(font.object_id()).unwrap();
+        let base_font = type0.get(b"BaseFont").unwrap().as_name_str().unwrap();
+        assert!(base_font.ends_with("+Cantarell-Bold-Identity-H"));
+
+        let light = FontType0Builder::from_file("assets/Cantarell-VF.otf")
+            .unwrap()
+            .variation("wght", 300.)
+            .add_to_doc(&mut doc);
+        assert!(light.text_width("Hello", 10.) < font.text_width("Hello", 10.));
+    }
+
+    #[test]
+    fn type0_vertical_uses_identity_v() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .vertical(true)
+            .subset(true)
+            .add_to_doc(&mut doc);
+        assert!(font.is_vertical());
+        assert_eq!(font.text_height("ab", 10.), 2. * font.text_height("a", 10.));
+        font.render_text("ab");
+        font.finish(&mut doc);
+
+        let type0 = doc.get_dictionary(font.object_id()).unwrap();
+        assert_eq!(
+            type0.get(b"Encoding").unwrap().as_name_str().unwrap(),
+            "Identity-V"
+        );
+        let descendant = type0.get(b"DescendantFonts").unwrap().as_array().unwrap()[0]
+            .as_reference()
+            .unwrap();
+        let descendant = doc.get_dictionary(descendant).unwrap();
+        let dw2 = descendant.get(b"DW2").unwrap().as_array().unwrap();
+        assert_eq!(dw2[1].as_i64().unwrap(), -1136);
+        assert!(descendant.get(b"W2").unwrap().as_array().is_ok());
+    }
+
+    #[test]
+    fn type0_to_unicode_covers_used_glyphs() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .add_to_doc(&mut doc);
+        font.render_text("abc z");
+        font.finish(&mut doc);
+
+        let type0 = doc.get_dictionary(font.object_id()).unwrap();
+        let to_unicode = type0.get(b"ToUnicode").unwrap().as_reference().unwrap();
+        let mut stream = doc
+            .get_object(to_unicode)
+            .unwrap()
+            .as_stream()
+            .unwrap()
+            .clone();
+        stream.decompress();
+        let cmap = String::from_utf8(stream.content).unwrap();
+        let gid = |c| font.face.as_face_ref().glyph_index(c).unwrap().0;
+        assert!(cmap.contains(&format!(
+            "1 beginbfrange\n<{:04X}> <{:04X}> <0061>",
+            gid('a'),
+            gid('c')
+        )));
+        assert!(cmap.contains("2 beginbfchar\n"));
+        assert!(!cmap.contains("<0064>"));
+    }
+
+    #[test]
+    fn type0_warns_about_missing_glyphs() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .add_to_doc(&mut doc);
+        font.render_text("a漢b");
+        assert_eq!(
+            font.finish(&mut doc),
+            vec!["Georgia: no glyph for \"漢\", shown as .notdef"]
+        );
+    }
+
+    #[test]
+    fn type0_shaping_does_not_subset_notdef() {
+        let mut doc = Document::with_version("1.5");
+        let font = FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .shaping(true)
+            .add_to_doc(&mut doc);
+        font.render_operations("a漢b");
+        assert!(!font.used_glyphs.borrow().contains_key(&0));
+        assert_eq!(font.used_glyphs.borrow().len(), 2);
+        assert_eq!(
+            font.finish(&mut doc),
+            vec!["Georgia: no glyph for \"漢\", shown as .notdef"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No named instance")]
+    fn type0_missing_named_instance() {
+        FontType0Builder::from_file("assets/Cantarell-VF.otf")
+            .unwrap()
+            .named_instance("Wide")
+            .add_to_doc(&mut Document::with_version("1.5"));
+    }
+
+    #[test]
+    fn type3_draws_glyphs() {
+        let mut doc = Document::with_version("1.5");
+        let font = type3()
+            .glyph('A', 600., |b| {
+                b.begin_path(0, 0)
+                    .append_straight_line(600, 700)
+                    .stroke_path()
+            })
+            .glyph('■', 1000., |b| b.begin_path(0, 0).fill_path())
+            .add_to_doc(&mut doc);
+
+        assert_eq!(font.render_text("A■")[0].as_str().unwrap(), &[0x41, 0x01]);
+        assert_eq!(font.text_width("A■", 10.), 16.);
+        let dict = doc.get_dictionary(font.object_id()).unwrap();
+        assert_eq!(dict.get(b"FirstChar").unwrap().as_i64().unwrap(), 1);
+        assert_eq!(dict.get(b"Widths").unwrap().as_array().unwrap().len(), 0x41);
+        let char_procs = dict.get(b"CharProcs").unwrap().as_dict().unwrap();
+        let glyph = char_procs.get(b"uni0041").unwrap().as_reference().unwrap();
+        let content = doc.get_object(glyph).unwrap().as_stream().unwrap();
+        assert!(content.content.starts_with(b"600 0 0 0 1000 1000 d1\n"));
+    }
+
+    #[test]
+    fn colour_glyphs_are_painted_by_type3_glyph_procedures() {
+        let mut doc = Document::with_version("1.5");
+        let font = colour(&std::fs::read("assets/NotoColorEmoji-Partial.ttf").unwrap())
+            .add_to_doc(&mut doc);
+        assert!(font.covers('©'));
+        assert!(!font.covers('A'));
+
+        assert_eq!(font.render_text("®©®")[0].as_str().unwrap(), &[1, 2, 1]);
+        font.finish(&mut doc);
+        let dict = doc.get_dictionary(font.object_id()).unwrap();
+        assert_eq!(
+            dict.get(b"Subtype").unwrap().as_name_str().unwrap(),
+            "Type3"
+        );
+        assert_eq!(dict.get(b"LastChar").unwrap().as_i64().unwrap(), 2);
+        let char_procs = dict.get(b"CharProcs").unwrap().as_dict().unwrap();
+        let glyph = char_procs.get(b"g16").unwrap().as_reference().unwrap();
+        let content = doc.get_object(glyph).unwrap().as_stream().unwrap();
+        assert_eq!(content.content, b"2550 0 d0\n/g16 Do");
+        let resources = dict.get(b"Resources").unwrap().as_dict().unwrap();
+        let forms = resources.get(b"XObject").unwrap().as_dict().unwrap();
+        assert!(forms.get(b"g16").is_ok());
+    }
+
+    #[test]
+    fn colour_codes_are_not_given_out_for_text_that_does_not_fit() {
+        let font = colour(&std::fs::read("assets/NotoColorEmoji-Partial.ttf").unwrap())
+            .add_to_doc(&mut Document::with_version("1.5"));
+        // use up all but one code with glyphs that are not in the text
+        font.used_glyphs
+            .borrow_mut()
+            .extend((1000..1254).map(|gid| (gid, ((gid - 999) as u8, String::new()))));
+
+        let err = font.try_render_text("©®").unwrap_err();
+        assert_eq!(err.character, '®');
+        assert_eq!(font.used_glyphs.borrow().len(), 254);
+        assert_eq!(font.render_text("©©")[0].as_str().unwrap(), &[255, 255]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Font has no COLR, sbix or CBDT table")]
+    fn colour_needs_colour_tables() {
+        colour(&std::fs::read("assets/Georgia.ttf").unwrap())
+            .add_to_doc(&mut Document::with_version("1.5"));
+    }
+
+    #[test]
+    fn true_type_runs_out_of_codes() {
+        let mut doc = Document::with_version("1.5");
+        let font = true_type(&std::fs::read("assets/Georgia.ttf").unwrap()).add_to_doc(&mut doc);
+        // WinAnsiEncoding leaves 37 codes for other characters
+        let cyrillic: String = ('А'..='Я').chain('а'..='д').collect();
+        assert_eq!(font.try_render_text(&cyrillic).unwrap().len(), 1);
+        // characters that already have a code can still be shown
+        assert!(font.try_render_text("Два").is_ok());
+        let err = font.try_render_text("ж").unwrap_err();
+        assert_eq!(err.character, 'ж');
+        assert_eq!(err.font, "Georgia");
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be encoded")]
+    fn type1_panics_on_unencodable_text() {
+        InternalFontReference::default().render_text("→");
+    }
+}
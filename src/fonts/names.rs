@@ -0,0 +1,81 @@
+//! PostScript font names
+//!
+//! The `BaseFont` and `FontName` entries should be the PostScript name of the
+//! font, prefixed with a subset tag when the font is subset. See section 9.6.2
+//! and 9.6.4 of the PDF spec.
+use owned_ttf_parser::{name_id, Face, PlatformId};
+
+/// PostScript names are limited to 63 characters.
+const MAX_NAME_LENGTH: usize = 63;
+
+/// Get the name to use for a font, with an optional subset tag.
+pub(crate) fn font_name(face: &Face, subset_tag: Option<&str>) -> String {
+    let name = postscript_name(face);
+    match subset_tag {
+        Some(tag) => format!("{}+{}", tag, name),
+        None => name,
+    }
+}
+
+/// Read the PostScript name from the `name` table.
+///
+/// Falls back to the full name and then the family name for fonts without a
+/// PostScript name.
+pub(crate) fn postscript_name(face: &Face) -> String {
+    [
+        name_id::POST_SCRIPT_NAME,
+        name_id::FULL_NAME,
+        name_id::FAMILY,
+    ]
+    .into_iter()
+    .filter_map(|id| name_string(face, id))
+    .map(|name| sanitise(&name))
+    .find(|name| !name.is_empty())
+    .unwrap_or_else(|| String::from("Unnamed"))
+}
+
+/// Read a name from the `name` table, in the first encoding that can be decoded.
+pub(crate) fn name_string(face: &Face, id: u16) -> Option<String> {
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == id)
+        .filter_map(|name| match name.platform_id {
+            PlatformId::Macintosh => Some(name.name.iter().map(|&b| b as char).collect()),
+            _ => name.to_string(),
+        })
+        .find(|name: &String| !name.is_empty())
+}
+
+/// Remove characters that are not allowed in a PostScript name.
+///
+/// Only printable ASCII is kept, without whitespace or PDF delimiters.
+pub(crate) fn sanitise(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_graphic() && !"[](){}<>/%#".contains(*c))
+        .take(MAX_NAME_LENGTH)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitise_names() {
+        assert_eq!(sanitise("Fira Code (Medium)"), "FiraCodeMedium");
+        assert_eq!(sanitise("Café/Bold#1"), "CafBold1");
+        assert_eq!(sanitise(&"A".repeat(100)).len(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn names_from_fonts() {
+        let data = std::fs::read("assets/Georgia.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        assert_eq!(font_name(&face, None), "Georgia");
+        assert_eq!(font_name(&face, Some("ABCDEF")), "ABCDEF+Georgia");
+
+        let data = std::fs::read("assets/FiraCodeNerdFontMono-Medium.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        assert!(postscript_name(&face).starts_with("FiraCode"));
+    }
+}
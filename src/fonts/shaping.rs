@@ -0,0 +1,195 @@
+//! Text shaping for Type0 fonts
+//!
+//! Uses `rustybuzz` to apply the `kern`, `GPOS` and `GSUB` tables so that text
+//! gets kerning and ligatures. The shaped glyphs are written as a `TJ` array
+//! where the numbers adjust the position of the next glyph relative to the
+//! widths in the font's `W` array. See section 9.4.3 of the PDF spec.
+use std::collections::BTreeSet;
+
+use lopdf::{Object, StringFormat};
+use owned_ttf_parser::{Face, GlyphId};
+
+/// A glyph produced by shaping, positioned relative to its default advance.
+pub(crate) struct ShapedGlyph {
+    pub glyph_id: u16,
+    /// Byte offset of the start of the cluster this glyph belongs to.
+    pub cluster: usize,
+    /// Horizontal offset to apply before drawing the glyph, in font units.
+    pub x_offset: i32,
+    /// Difference between the shaped and default advance, in font units.
+    pub advance_adjustment: i32,
+}
+
+/// Shape a string of text with the default features of the font.
+pub(crate) fn shape(face: &Face, text: &str) -> Vec<ShapedGlyph> {
+    let shaper = rustybuzz::Face::from_face(face.clone());
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    let output = rustybuzz::shape(&shaper, &[], buffer);
+
+    output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| {
+            let glyph_id = info.glyph_id as u16;
+            let default_advance = face.glyph_hor_advance(GlyphId(glyph_id)).unwrap_or(0);
+            ShapedGlyph {
+                glyph_id,
+                cluster: info.cluster as usize,
+                x_offset: position.x_offset,
+                advance_adjustment: position.x_advance - default_advance as i32,
+            }
+        })
+        .collect()
+}
+
+/// Shape text set top to bottom.
+///
+/// This substitutes vertical forms of glyphs such as punctuation and brackets
+/// with the `vert` feature. Positioning is left to the `W2` array so the
+/// glyphs have no adjustments.
+pub(crate) fn shape_vertical(face: &Face, text: &str) -> Vec<ShapedGlyph> {
+    let shaper = rustybuzz::Face::from_face(face.clone());
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(rustybuzz::Direction::TopToBottom);
+    let output = rustybuzz::shape(&shaper, &[], buffer);
+
+    output
+        .glyph_infos()
+        .iter()
+        .map(|info| ShapedGlyph {
+            glyph_id: info.glyph_id as u16,
+            cluster: info.cluster as usize,
+            x_offset: 0,
+            advance_adjustment: 0,
+        })
+        .collect()
+}
+
+/// The text that each shaped glyph represents, for the `ToUnicode` CMap.
+///
+/// A cluster runs up to the start of the next cluster in the text, so a
+/// ligature gets all the characters it replaced. When a cluster has several
+/// glyphs the text goes to the first and the rest get an empty string.
+pub(crate) fn cluster_texts<'t>(text: &'t str, glyphs: &[ShapedGlyph]) -> Vec<&'t str> {
+    let starts: BTreeSet<usize> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+    let mut seen = BTreeSet::new();
+    glyphs
+        .iter()
+        .map(|glyph| {
+            if !seen.insert(glyph.cluster) {
+                return "";
+            }
+            let end = starts
+                .range(glyph.cluster + 1..)
+                .next()
+                .copied()
+                .unwrap_or(text.len());
+            &text[glyph.cluster..end]
+        })
+        .collect()
+}
+
+/// Create the contents of a `TJ` array for shaped glyphs.
+///
+/// Vertical offsets can not be expressed in a `TJ` array and are ignored.
+pub(crate) fn text_items(face: &Face, glyphs: &[ShapedGlyph]) -> Vec<Object> {
+    let scale = 1000.0 / face.units_per_em() as f32;
+    let mut items = Vec::new();
+    let mut codes = Vec::new();
+    // how far right to move before the next glyph, in font units
+    let mut shift = 0;
+
+    for glyph in glyphs {
+        shift += glyph.x_offset;
+        if shift != 0 {
+            if !codes.is_empty() {
+                items.push(Object::String(
+                    std::mem::take(&mut codes),
+                    StringFormat::Hexadecimal,
+                ));
+            }
+            items.push(Object::Real(-(shift as f32) * scale));
+        }
+        codes.extend(glyph.glyph_id.to_be_bytes());
+        shift = glyph.advance_adjustment - glyph.x_offset;
+    }
+    if !codes.is_empty() {
+        items.push(Object::String(codes, StringFormat::Hexadecimal));
+    }
+    items
+}
+
+/// Encode text as a PDF text string in UTF-16BE with a byte order mark.
+///
+/// See section 7.9.2.2 of the PDF spec.
+pub(crate) fn text_string(text: &str) -> Object {
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(|c| c.to_be_bytes()));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_items_include_adjustments() {
+        let data = std::fs::read("assets/Georgia.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let upem = face.units_per_em() as i32;
+        let glyph = |glyph_id, x_offset, advance_adjustment| ShapedGlyph {
+            glyph_id,
+            cluster: 0,
+            x_offset,
+            advance_adjustment,
+        };
+        let glyphs = [glyph(36, 0, -upem / 10), glyph(57, 0, 0), glyph(36, 0, 0)];
+        let items = text_items(&face, &glyphs);
+
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[0].as_str().unwrap(), &[0, 36]);
+        assert_eq!(items[1].as_f32().unwrap().round(), 100.);
+        assert_eq!(items[2].as_str().unwrap(), &[0, 57, 0, 36]);
+
+        let plain = shape(&face, "AVA");
+        assert_eq!(text_items(&face, &plain).len(), 1);
+    }
+
+    #[test]
+    fn shaping_applies_ligatures() {
+        let data = std::fs::read("assets/FiraCodeNerdFontMono-Medium.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let glyphs = shape(&face, "a -> b");
+        let plain: Vec<u16> = "a -> b"
+            .chars()
+            .map(|c| face.glyph_index(c).unwrap().0)
+            .collect();
+
+        assert_ne!(glyphs.iter().map(|g| g.glyph_id).collect::<Vec<_>>(), plain);
+        assert_eq!(glyphs[2].cluster, 2);
+    }
+
+    #[test]
+    fn clusters_map_to_text() {
+        let glyph = |glyph_id, cluster| ShapedGlyph {
+            glyph_id,
+            cluster,
+            x_offset: 0,
+            advance_adjustment: 0,
+        };
+        // an "ffi" ligature, a space and an "é" decomposed into two glyphs
+        let glyphs = [glyph(1, 0), glyph(2, 3), glyph(3, 4), glyph(4, 4)];
+        assert_eq!(cluster_texts("ffi é", &glyphs), vec!["ffi", " ", "é", ""]);
+    }
+
+    #[test]
+    fn text_string_has_bom() {
+        let Object::String(bytes, _) = text_string("é") else {
+            panic!("expected a string");
+        };
+        assert_eq!(bytes, vec![0xFE, 0xFF, 0x00, 0xE9]);
+    }
+}
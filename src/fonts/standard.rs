@@ -0,0 +1,704 @@
+//! Metrics for the standard 14 fonts
+//!
+//! Viewers must provide these fonts so they are not embedded, but their widths
+//! are still needed to measure text. The widths come from the Adobe Core 14 AFM
+//! files and are indexed by code in `WinAnsiEncoding`, or in the built in
+//! encoding of `Symbol` and `ZapfDingbats`. See section 9.6.2.2 of the PDF spec.
+use super::encoding::win_ansi_code;
+
+pub(crate) struct StandardFont {
+    pub name: &'static str,
+    pub ascent: i64,
+    pub descent: i64,
+    /// The built in encoding of a symbolic font, used instead of
+    /// `WinAnsiEncoding`.
+    builtin_encoding: Option<&'static [(u8, char)]>,
+    widths: &'static [u16; 256],
+}
+
+impl StandardFont {
+    /// Whether the font uses its own built in encoding.
+    pub fn is_symbolic(&self) -> bool {
+        self.builtin_encoding.is_some()
+    }
+
+    /// Get the code for a character in the font's encoding.
+    pub fn encode(&self, c: char) -> Option<u8> {
+        match self.builtin_encoding {
+            Some(encoding) => encoding
+                .iter()
+                .find(|&&(_, x)| x == c)
+                .map(|&(code, _)| code),
+            None => win_ansi_code(c),
+        }
+    }
+
+    /// The width of the glyph for a code, in glyph space.
+    pub fn width(&self, code: u8) -> u16 {
+        self.widths[code as usize]
+    }
+}
+
+/// Look up one of the standard 14 fonts by its `BaseFont` name.
+pub(crate) fn standard_font(name: &str) -> Option<&'static StandardFont> {
+    STANDARD_FONTS.iter().find(|font| font.name == name)
+}
+
+static STANDARD_FONTS: [StandardFont; 14] = [
+    StandardFont {
+        name: "Courier",
+        ascent: 629,
+        descent: -157,
+        builtin_encoding: None,
+        widths: &COURIER_WIDTHS,
+    },
+    StandardFont {
+        name: "Courier-Bold",
+        ascent: 629,
+        descent: -157,
+        builtin_encoding: None,
+        widths: &COURIER_WIDTHS,
+    },
+    StandardFont {
+        name: "Courier-Oblique",
+        ascent: 629,
+        descent: -157,
+        builtin_encoding: None,
+        widths: &COURIER_WIDTHS,
+    },
+    StandardFont {
+        name: "Courier-BoldOblique",
+        ascent: 629,
+        descent: -157,
+        builtin_encoding: None,
+        widths: &COURIER_WIDTHS,
+    },
+    StandardFont {
+        name: "Helvetica",
+        ascent: 718,
+        descent: -207,
+        builtin_encoding: None,
+        widths: &HELVETICA_WIDTHS,
+    },
+    StandardFont {
+        name: "Helvetica-Bold",
+        ascent: 718,
+        descent: -207,
+        builtin_encoding: None,
+        widths: &HELVETICA_BOLD_WIDTHS,
+    },
+    StandardFont {
+        name: "Helvetica-Oblique",
+        ascent: 718,
+        descent: -207,
+        builtin_encoding: None,
+        widths: &HELVETICA_WIDTHS,
+    },
+    StandardFont {
+        name: "Helvetica-BoldOblique",
+        ascent: 718,
+        descent: -207,
+        builtin_encoding: None,
+        widths: &HELVETICA_BOLD_WIDTHS,
+    },
+    StandardFont {
+        name: "Times-Roman",
+        ascent: 683,
+        descent: -217,
+        builtin_encoding: None,
+        widths: &TIMES_ROMAN_WIDTHS,
+    },
+    StandardFont {
+        name: "Times-Bold",
+        ascent: 683,
+        descent: -217,
+        builtin_encoding: None,
+        widths: &TIMES_BOLD_WIDTHS,
+    },
+    StandardFont {
+        name: "Times-Italic",
+        ascent: 683,
+        descent: -217,
+        builtin_encoding: None,
+        widths: &TIMES_ITALIC_WIDTHS,
+    },
+    StandardFont {
+        name: "Times-BoldItalic",
+        ascent: 683,
+        descent: -217,
+        builtin_encoding: None,
+        widths: &TIMES_BOLDITALIC_WIDTHS,
+    },
+    StandardFont {
+        name: "Symbol",
+        ascent: 1010,
+        descent: -293,
+        builtin_encoding: Some(&SYMBOL_ENCODING),
+        widths: &SYMBOL_WIDTHS,
+    },
+    StandardFont {
+        name: "ZapfDingbats",
+        ascent: 820,
+        descent: -143,
+        builtin_encoding: Some(&ZAPFDINGBATS_ENCODING),
+        widths: &ZAPFDINGBATS_WIDTHS,
+    },
+];
+
+const COURIER_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+    600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600, 600,
+];
+
+const HELVETICA_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+    350, 556, 350, 222, 556, 333, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350, 350,
+    222, 222, 333, 333, 350, 556, 1000, 333, 1000, 500, 333, 944, 350, 500, 667, 278, 333, 556,
+    556, 556, 556, 260, 556, 333, 737, 370, 556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 556,
+    537, 278, 333, 333, 365, 556, 834, 834, 834, 611, 667, 667, 667, 667, 667, 667, 1000, 722, 667,
+    667, 667, 667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722,
+    722, 667, 667, 611, 556, 556, 556, 556, 556, 556, 889, 500, 556, 556, 556, 556, 278, 278, 278,
+    278, 556, 556, 556, 556, 556, 556, 556, 584, 611, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const HELVETICA_BOLD_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+    350, 556, 350, 278, 556, 500, 1000, 556, 556, 333, 1000, 667, 333, 1000, 350, 611, 350, 350,
+    278, 278, 500, 500, 350, 556, 1000, 333, 1000, 556, 333, 944, 350, 500, 667, 278, 333, 556,
+    556, 556, 556, 280, 556, 333, 737, 370, 556, 584, 333, 737, 333, 400, 584, 333, 333, 333, 611,
+    556, 278, 333, 333, 365, 556, 834, 834, 834, 611, 722, 722, 722, 722, 722, 722, 1000, 722, 667,
+    667, 667, 667, 278, 278, 278, 278, 722, 722, 778, 778, 778, 778, 778, 584, 778, 722, 722, 722,
+    722, 667, 667, 611, 556, 556, 556, 556, 556, 556, 889, 556, 556, 556, 556, 556, 278, 278, 278,
+    278, 611, 611, 611, 611, 611, 611, 611, 584, 611, 611, 611, 611, 611, 556, 611, 556,
+];
+
+const TIMES_ROMAN_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611,
+    556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722,
+    722, 611, 333, 278, 333, 469, 500, 333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500,
+    278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+    350, 500, 350, 333, 500, 444, 1000, 500, 500, 333, 1000, 556, 333, 889, 350, 611, 350, 350,
+    333, 333, 444, 444, 350, 500, 1000, 333, 980, 389, 333, 722, 350, 444, 722, 250, 333, 500, 500,
+    500, 500, 200, 500, 333, 760, 276, 500, 564, 333, 760, 333, 400, 564, 300, 300, 333, 500, 453,
+    250, 333, 300, 310, 500, 750, 750, 750, 444, 722, 722, 722, 722, 722, 722, 889, 667, 611, 611,
+    611, 611, 333, 333, 333, 333, 722, 722, 722, 722, 722, 722, 722, 564, 722, 722, 722, 722, 722,
+    722, 556, 500, 444, 444, 444, 444, 444, 444, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278,
+    500, 500, 500, 500, 500, 500, 500, 564, 500, 500, 500, 500, 500, 500, 500, 500,
+];
+
+const TIMES_BOLD_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    250, 333, 555, 500, 500, 1000, 833, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 930, 722, 667, 722, 722, 667,
+    611, 778, 778, 389, 500, 778, 667, 944, 722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722,
+    722, 667, 333, 278, 333, 581, 500, 333, 500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556,
+    278, 833, 556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394, 220, 394, 520,
+    350, 500, 350, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 1000, 350, 667, 350, 350,
+    333, 333, 500, 500, 350, 500, 1000, 333, 1000, 389, 333, 722, 350, 444, 722, 250, 333, 500,
+    500, 500, 500, 220, 500, 333, 747, 300, 500, 570, 333, 747, 333, 400, 570, 300, 300, 333, 556,
+    540, 250, 333, 300, 330, 500, 750, 750, 750, 500, 722, 722, 722, 722, 722, 722, 1000, 722, 667,
+    667, 667, 667, 389, 389, 389, 389, 722, 722, 778, 778, 778, 778, 778, 570, 778, 722, 722, 722,
+    722, 722, 611, 556, 500, 500, 500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278,
+    278, 500, 556, 500, 500, 500, 500, 500, 570, 500, 556, 556, 556, 556, 500, 556, 500,
+];
+
+const TIMES_ITALIC_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    250, 333, 420, 500, 500, 833, 778, 214, 333, 333, 500, 675, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 675, 675, 675, 500, 920, 611, 611, 667, 722, 611,
+    611, 722, 722, 333, 444, 667, 556, 833, 667, 722, 611, 722, 611, 500, 556, 722, 611, 833, 611,
+    556, 556, 389, 278, 389, 422, 500, 333, 500, 500, 444, 500, 444, 278, 500, 500, 278, 278, 444,
+    278, 722, 500, 500, 500, 500, 389, 389, 278, 500, 444, 667, 444, 444, 389, 400, 275, 400, 541,
+    350, 500, 350, 333, 500, 556, 889, 500, 500, 333, 1000, 500, 333, 944, 350, 556, 350, 350, 333,
+    333, 556, 556, 350, 500, 889, 333, 980, 389, 333, 667, 350, 389, 556, 250, 389, 500, 500, 500,
+    500, 275, 500, 333, 760, 276, 500, 675, 333, 760, 333, 400, 675, 300, 300, 333, 500, 523, 250,
+    333, 300, 310, 500, 750, 750, 750, 500, 611, 611, 611, 611, 611, 611, 889, 667, 611, 611, 611,
+    611, 333, 333, 333, 333, 722, 667, 722, 722, 722, 722, 722, 675, 722, 722, 722, 722, 722, 556,
+    611, 500, 500, 500, 500, 500, 500, 500, 667, 444, 444, 444, 444, 444, 278, 278, 278, 278, 500,
+    500, 500, 500, 500, 500, 500, 675, 500, 500, 500, 500, 500, 444, 500, 444,
+];
+
+const TIMES_BOLDITALIC_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    250, 389, 555, 500, 500, 833, 778, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 832, 667, 667, 667, 722, 667,
+    667, 722, 778, 389, 500, 667, 611, 889, 722, 722, 611, 722, 667, 556, 611, 722, 667, 889, 667,
+    611, 611, 333, 278, 333, 570, 500, 333, 500, 500, 444, 500, 444, 333, 500, 556, 278, 278, 500,
+    278, 778, 556, 500, 500, 500, 389, 389, 278, 556, 444, 667, 500, 444, 389, 348, 220, 348, 570,
+    350, 500, 350, 333, 500, 500, 1000, 500, 500, 333, 1000, 556, 333, 944, 350, 611, 350, 350,
+    333, 333, 500, 500, 350, 500, 1000, 333, 1000, 389, 333, 722, 350, 389, 611, 250, 389, 500,
+    500, 500, 500, 220, 500, 333, 747, 266, 500, 606, 333, 747, 333, 400, 570, 300, 300, 333, 576,
+    500, 250, 333, 300, 300, 500, 750, 750, 750, 500, 667, 667, 667, 667, 667, 667, 944, 667, 667,
+    667, 667, 667, 389, 389, 389, 389, 722, 722, 722, 722, 722, 722, 722, 570, 722, 722, 722, 722,
+    722, 611, 611, 500, 500, 500, 500, 500, 500, 500, 722, 444, 444, 444, 444, 444, 278, 278, 278,
+    278, 500, 556, 500, 500, 500, 500, 500, 570, 500, 556, 556, 556, 556, 444, 500, 444,
+];
+
+const SYMBOL_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    250, 333, 713, 500, 549, 833, 778, 439, 333, 333, 500, 549, 250, 549, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 549, 549, 549, 444, 549, 722, 667, 722, 612, 611,
+    763, 603, 722, 333, 631, 722, 686, 889, 722, 722, 768, 741, 556, 592, 611, 690, 439, 768, 645,
+    795, 611, 333, 863, 333, 658, 500, 500, 631, 549, 549, 494, 439, 521, 411, 603, 329, 603, 549,
+    549, 576, 521, 549, 549, 521, 549, 603, 439, 576, 713, 686, 493, 686, 494, 480, 200, 480, 549,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 750, 620, 247, 549, 167, 713, 500, 753, 753, 753, 753, 1042, 987, 603, 987, 603, 400, 549,
+    411, 549, 549, 713, 494, 460, 549, 549, 549, 549, 1000, 603, 1000, 658, 823, 686, 795, 987,
+    768, 768, 823, 768, 768, 713, 713, 713, 713, 713, 713, 713, 768, 713, 790, 790, 890, 823, 549,
+    250, 713, 603, 603, 1042, 987, 603, 987, 603, 494, 329, 790, 790, 786, 713, 384, 384, 384, 384,
+    384, 384, 494, 494, 494, 494, 0, 329, 274, 686, 686, 686, 384, 384, 384, 384, 384, 384, 494,
+    494, 494, 0,
+];
+
+const ZAPFDINGBATS_WIDTHS: [u16; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    278, 974, 961, 974, 980, 719, 789, 790, 791, 690, 960, 939, 549, 855, 911, 933, 911, 945, 974,
+    755, 846, 762, 761, 571, 677, 763, 760, 759, 754, 494, 552, 537, 577, 692, 786, 788, 788, 790,
+    793, 794, 816, 823, 789, 841, 823, 833, 816, 831, 923, 744, 723, 749, 790, 792, 695, 776, 768,
+    792, 759, 707, 708, 682, 701, 826, 815, 789, 789, 707, 687, 696, 689, 786, 787, 713, 791, 785,
+    791, 873, 761, 762, 762, 759, 759, 892, 892, 788, 784, 438, 138, 277, 415, 392, 392, 668, 668,
+    0, 390, 390, 317, 317, 276, 276, 509, 509, 410, 410, 234, 234, 334, 334, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 732, 544, 544, 910, 667, 760, 760, 776, 595, 694, 626, 788,
+    788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788,
+    788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788, 788,
+    788, 894, 838, 1016, 458, 748, 924, 748, 918, 927, 928, 928, 834, 873, 828, 924, 924, 917, 930,
+    931, 463, 883, 836, 836, 867, 867, 696, 696, 874, 0, 874, 760, 946, 771, 865, 771, 888, 967,
+    888, 831, 873, 927, 970, 918, 0,
+];
+
+/// Characters for the codes in the built in encoding of `Symbol`.
+const SYMBOL_ENCODING: [(u8, char); 188] = [
+    (32, '\u{20}'),
+    (33, '!'),
+    (34, '\u{2200}'),
+    (35, '#'),
+    (36, '\u{2203}'),
+    (37, '%'),
+    (38, '&'),
+    (39, '\u{220B}'),
+    (40, '('),
+    (41, ')'),
+    (42, '\u{2217}'),
+    (43, '+'),
+    (44, ','),
+    (45, '\u{2212}'),
+    (46, '.'),
+    (47, '/'),
+    (48, '0'),
+    (49, '1'),
+    (50, '2'),
+    (51, '3'),
+    (52, '4'),
+    (53, '5'),
+    (54, '6'),
+    (55, '7'),
+    (56, '8'),
+    (57, '9'),
+    (58, ':'),
+    (59, ';'),
+    (60, '<'),
+    (61, '='),
+    (62, '>'),
+    (63, '?'),
+    (64, '\u{2245}'),
+    (65, '\u{391}'),
+    (66, '\u{392}'),
+    (67, '\u{3A7}'),
+    (68, '\u{2206}'),
+    (69, '\u{395}'),
+    (70, '\u{3A6}'),
+    (71, '\u{393}'),
+    (72, '\u{397}'),
+    (73, '\u{399}'),
+    (74, '\u{3D1}'),
+    (75, '\u{39A}'),
+    (76, '\u{39B}'),
+    (77, '\u{39C}'),
+    (78, '\u{39D}'),
+    (79, '\u{39F}'),
+    (80, '\u{3A0}'),
+    (81, '\u{398}'),
+    (82, '\u{3A1}'),
+    (83, '\u{3A3}'),
+    (84, '\u{3A4}'),
+    (85, '\u{3A5}'),
+    (86, '\u{3C2}'),
+    (87, '\u{2126}'),
+    (88, '\u{39E}'),
+    (89, '\u{3A8}'),
+    (90, '\u{396}'),
+    (91, '['),
+    (92, '\u{2234}'),
+    (93, ']'),
+    (94, '\u{22A5}'),
+    (95, '_'),
+    (96, '\u{F8E5}'),
+    (97, '\u{3B1}'),
+    (98, '\u{3B2}'),
+    (99, '\u{3C7}'),
+    (100, '\u{3B4}'),
+    (101, '\u{3B5}'),
+    (102, '\u{3C6}'),
+    (103, '\u{3B3}'),
+    (104, '\u{3B7}'),
+    (105, '\u{3B9}'),
+    (106, '\u{3D5}'),
+    (107, '\u{3BA}'),
+    (108, '\u{3BB}'),
+    (109, '\u{B5}'),
+    (110, '\u{3BD}'),
+    (111, '\u{3BF}'),
+    (112, '\u{3C0}'),
+    (113, '\u{3B8}'),
+    (114, '\u{3C1}'),
+    (115, '\u{3C3}'),
+    (116, '\u{3C4}'),
+    (117, '\u{3C5}'),
+    (118, '\u{3D6}'),
+    (119, '\u{3C9}'),
+    (120, '\u{3BE}'),
+    (121, '\u{3C8}'),
+    (122, '\u{3B6}'),
+    (123, '{'),
+    (124, '|'),
+    (125, '}'),
+    (126, '\u{223C}'),
+    (161, '\u{3D2}'),
+    (162, '\u{2032}'),
+    (163, '\u{2264}'),
+    (164, '\u{2044}'),
+    (165, '\u{221E}'),
+    (166, '\u{192}'),
+    (167, '\u{2663}'),
+    (168, '\u{2666}'),
+    (169, '\u{2665}'),
+    (170, '\u{2660}'),
+    (171, '\u{2194}'),
+    (172, '\u{2190}'),
+    (173, '\u{2191}'),
+    (174, '\u{2192}'),
+    (175, '\u{2193}'),
+    (176, '\u{B0}'),
+    (177, '\u{B1}'),
+    (178, '\u{2033}'),
+    (179, '\u{2265}'),
+    (180, '\u{D7}'),
+    (181, '\u{221D}'),
+    (182, '\u{2202}'),
+    (183, '\u{2022}'),
+    (184, '\u{F7}'),
+    (185, '\u{2260}'),
+    (186, '\u{2261}'),
+    (187, '\u{2248}'),
+    (188, '\u{2026}'),
+    (189, '\u{F8E6}'),
+    (190, '\u{F8E7}'),
+    (191, '\u{21B5}'),
+    (192, '\u{2135}'),
+    (193, '\u{2111}'),
+    (194, '\u{211C}'),
+    (195, '\u{2118}'),
+    (196, '\u{2297}'),
+    (197, '\u{2295}'),
+    (198, '\u{2205}'),
+    (199, '\u{2229}'),
+    (200, '\u{222A}'),
+    (201, '\u{2283}'),
+    (202, '\u{2287}'),
+    (203, '\u{2284}'),
+    (204, '\u{2282}'),
+    (205, '\u{2286}'),
+    (206, '\u{2208}'),
+    (207, '\u{2209}'),
+    (208, '\u{2220}'),
+    (209, '\u{2207}'),
+    (210, '\u{F6DA}'),
+    (211, '\u{F6D9}'),
+    (212, '\u{F6DB}'),
+    (213, '\u{220F}'),
+    (214, '\u{221A}'),
+    (215, '\u{22C5}'),
+    (216, '\u{AC}'),
+    (217, '\u{2227}'),
+    (218, '\u{2228}'),
+    (219, '\u{21D4}'),
+    (220, '\u{21D0}'),
+    (221, '\u{21D1}'),
+    (222, '\u{21D2}'),
+    (223, '\u{21D3}'),
+    (224, '\u{25CA}'),
+    (225, '\u{2329}'),
+    (226, '\u{F8E8}'),
+    (227, '\u{F8E9}'),
+    (228, '\u{F8EA}'),
+    (229, '\u{2211}'),
+    (230, '\u{F8EB}'),
+    (231, '\u{F8EC}'),
+    (232, '\u{F8ED}'),
+    (233, '\u{F8EE}'),
+    (234, '\u{F8EF}'),
+    (235, '\u{F8F0}'),
+    (236, '\u{F8F1}'),
+    (237, '\u{F8F2}'),
+    (238, '\u{F8F3}'),
+    (239, '\u{F8F4}'),
+    (241, '\u{232A}'),
+    (242, '\u{222B}'),
+    (243, '\u{2320}'),
+    (244, '\u{F8F5}'),
+    (245, '\u{2321}'),
+    (246, '\u{F8F6}'),
+    (247, '\u{F8F7}'),
+    (248, '\u{F8F8}'),
+    (249, '\u{F8F9}'),
+    (250, '\u{F8FA}'),
+    (251, '\u{F8FB}'),
+    (252, '\u{F8FC}'),
+    (253, '\u{F8FD}'),
+    (254, '\u{F8FE}'),
+];
+
+/// Characters for the codes in the built in encoding of `ZapfDingbats`.
+const ZAPFDINGBATS_ENCODING: [(u8, char); 188] = [
+    (32, '\u{20}'),
+    (33, '\u{2701}'),
+    (34, '\u{2702}'),
+    (35, '\u{2703}'),
+    (36, '\u{2704}'),
+    (37, '\u{260E}'),
+    (38, '\u{2706}'),
+    (39, '\u{2707}'),
+    (40, '\u{2708}'),
+    (41, '\u{2709}'),
+    (42, '\u{261B}'),
+    (43, '\u{261E}'),
+    (44, '\u{270C}'),
+    (45, '\u{270D}'),
+    (46, '\u{270E}'),
+    (47, '\u{270F}'),
+    (48, '\u{2710}'),
+    (49, '\u{2711}'),
+    (50, '\u{2712}'),
+    (51, '\u{2713}'),
+    (52, '\u{2714}'),
+    (53, '\u{2715}'),
+    (54, '\u{2716}'),
+    (55, '\u{2717}'),
+    (56, '\u{2718}'),
+    (57, '\u{2719}'),
+    (58, '\u{271A}'),
+    (59, '\u{271B}'),
+    (60, '\u{271C}'),
+    (61, '\u{271D}'),
+    (62, '\u{271E}'),
+    (63, '\u{271F}'),
+    (64, '\u{2720}'),
+    (65, '\u{2721}'),
+    (66, '\u{2722}'),
+    (67, '\u{2723}'),
+    (68, '\u{2724}'),
+    (69, '\u{2725}'),
+    (70, '\u{2726}'),
+    (71, '\u{2727}'),
+    (72, '\u{2605}'),
+    (73, '\u{2729}'),
+    (74, '\u{272A}'),
+    (75, '\u{272B}'),
+    (76, '\u{272C}'),
+    (77, '\u{272D}'),
+    (78, '\u{272E}'),
+    (79, '\u{272F}'),
+    (80, '\u{2730}'),
+    (81, '\u{2731}'),
+    (82, '\u{2732}'),
+    (83, '\u{2733}'),
+    (84, '\u{2734}'),
+    (85, '\u{2735}'),
+    (86, '\u{2736}'),
+    (87, '\u{2737}'),
+    (88, '\u{2738}'),
+    (89, '\u{2739}'),
+    (90, '\u{273A}'),
+    (91, '\u{273B}'),
+    (92, '\u{273C}'),
+    (93, '\u{273D}'),
+    (94, '\u{273E}'),
+    (95, '\u{273F}'),
+    (96, '\u{2740}'),
+    (97, '\u{2741}'),
+    (98, '\u{2742}'),
+    (99, '\u{2743}'),
+    (100, '\u{2744}'),
+    (101, '\u{2745}'),
+    (102, '\u{2746}'),
+    (103, '\u{2747}'),
+    (104, '\u{2748}'),
+    (105, '\u{2749}'),
+    (106, '\u{274A}'),
+    (107, '\u{274B}'),
+    (108, '\u{25CF}'),
+    (109, '\u{274D}'),
+    (110, '\u{25A0}'),
+    (111, '\u{274F}'),
+    (112, '\u{2750}'),
+    (113, '\u{2751}'),
+    (114, '\u{2752}'),
+    (115, '\u{25B2}'),
+    (116, '\u{25BC}'),
+    (117, '\u{25C6}'),
+    (118, '\u{2756}'),
+    (119, '\u{25D7}'),
+    (120, '\u{2758}'),
+    (121, '\u{2759}'),
+    (122, '\u{275A}'),
+    (123, '\u{275B}'),
+    (124, '\u{275C}'),
+    (125, '\u{275D}'),
+    (126, '\u{275E}'),
+    (161, '\u{2761}'),
+    (162, '\u{2762}'),
+    (163, '\u{2763}'),
+    (164, '\u{2764}'),
+    (165, '\u{2765}'),
+    (166, '\u{2766}'),
+    (167, '\u{2767}'),
+    (168, '\u{2663}'),
+    (169, '\u{2666}'),
+    (170, '\u{2665}'),
+    (171, '\u{2660}'),
+    (172, '\u{2460}'),
+    (173, '\u{2461}'),
+    (174, '\u{2462}'),
+    (175, '\u{2463}'),
+    (176, '\u{2464}'),
+    (177, '\u{2465}'),
+    (178, '\u{2466}'),
+    (179, '\u{2467}'),
+    (180, '\u{2468}'),
+    (181, '\u{2469}'),
+    (182, '\u{2776}'),
+    (183, '\u{2777}'),
+    (184, '\u{2778}'),
+    (185, '\u{2779}'),
+    (186, '\u{277A}'),
+    (187, '\u{277B}'),
+    (188, '\u{277C}'),
+    (189, '\u{277D}'),
+    (190, '\u{277E}'),
+    (191, '\u{277F}'),
+    (192, '\u{2780}'),
+    (193, '\u{2781}'),
+    (194, '\u{2782}'),
+    (195, '\u{2783}'),
+    (196, '\u{2784}'),
+    (197, '\u{2785}'),
+    (198, '\u{2786}'),
+    (199, '\u{2787}'),
+    (200, '\u{2788}'),
+    (201, '\u{2789}'),
+    (202, '\u{278A}'),
+    (203, '\u{278B}'),
+    (204, '\u{278C}'),
+    (205, '\u{278D}'),
+    (206, '\u{278E}'),
+    (207, '\u{278F}'),
+    (208, '\u{2790}'),
+    (209, '\u{2791}'),
+    (210, '\u{2792}'),
+    (211, '\u{2793}'),
+    (212, '\u{2794}'),
+    (213, '\u{2192}'),
+    (214, '\u{2194}'),
+    (215, '\u{2195}'),
+    (216, '\u{2798}'),
+    (217, '\u{2799}'),
+    (218, '\u{279A}'),
+    (219, '\u{279B}'),
+    (220, '\u{279C}'),
+    (221, '\u{279D}'),
+    (222, '\u{279E}'),
+    (223, '\u{279F}'),
+    (224, '\u{27A0}'),
+    (225, '\u{27A1}'),
+    (226, '\u{27A2}'),
+    (227, '\u{27A3}'),
+    (228, '\u{27A4}'),
+    (229, '\u{27A5}'),
+    (230, '\u{27A6}'),
+    (231, '\u{27A7}'),
+    (232, '\u{27A8}'),
+    (233, '\u{27A9}'),
+    (234, '\u{27AA}'),
+    (235, '\u{27AB}'),
+    (236, '\u{27AC}'),
+    (237, '\u{27AD}'),
+    (238, '\u{27AE}'),
+    (239, '\u{27AF}'),
+    (241, '\u{27B1}'),
+    (242, '\u{27B2}'),
+    (243, '\u{27B3}'),
+    (244, '\u{27B4}'),
+    (245, '\u{27B5}'),
+    (246, '\u{27B6}'),
+    (247, '\u{27B7}'),
+    (248, '\u{27B8}'),
+    (249, '\u{27B9}'),
+    (250, '\u{27BA}'),
+    (251, '\u{27BB}'),
+    (252, '\u{27BC}'),
+    (253, '\u{27BD}'),
+    (254, '\u{27BE}'),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_font_widths() {
+        let helvetica = standard_font("Helvetica").unwrap();
+        assert_eq!(helvetica.width(b' '), 278);
+        assert_eq!(helvetica.width(b'W'), 944);
+        assert_eq!(helvetica.width(0x80), 556);
+        assert!(!helvetica.is_symbolic());
+        assert_eq!(helvetica.encode('é'), Some(0xE9));
+        assert_eq!(helvetica.encode('–'), Some(0x96));
+        assert_eq!(helvetica.encode('α'), None);
+
+        let courier = standard_font("Courier-Bold").unwrap();
+        assert!((b' '..=b'~').all(|code| courier.width(code) == 600));
+
+        let symbol = standard_font("Symbol").unwrap();
+        assert!(symbol.is_symbolic());
+        assert_eq!(symbol.encode('α'), Some(b'a'));
+        assert_eq!(symbol.encode('é'), None);
+        assert_eq!(
+            standard_font("ZapfDingbats").unwrap().encode('✓'),
+            Some(0x33)
+        );
+        assert!(standard_font("Arial").is_none());
+    }
+}
@@ -0,0 +1,494 @@
+//! TrueType glyph subsetting
+//!
+//! Builds a new font program containing only the glyphs that were used in a
+//! document. Glyph IDs are left unchanged so that the CIDs already written into
+//! content streams, the `W` array and the `ToUnicode` CMap all stay valid. Unused
+//! glyphs are emptied out of `glyf` rather than removed.
+use std::collections::{BTreeMap, BTreeSet};
+
+use owned_ttf_parser::{head::IndexToLocationFormat, Face, Tag};
+
+/// Tables that are copied into the subset unchanged.
+///
+/// Everything else (layout, colour, variation tables etc.) is not needed to
+/// render glyphs from a PDF and is dropped.
+const COPIED_TABLES: [&[u8; 4]; 6] = [b"OS/2", b"cvt ", b"fpgm", b"gasp", b"name", b"prep"];
+
+// Composite glyph flags, see the `glyf` table in the OpenType spec.
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// Create a subset of a `glyf` based font.
+///
+/// `glyphs` are the glyphs that were used. Glyph 0 (`.notdef`) and any
+/// components of composite glyphs are always kept.
+pub(crate) fn subset(face: &Face, glyphs: &BTreeSet<u16>) -> Vec<u8> {
+    let keep = glyph_closure(face, glyphs.iter().copied());
+    let mut tables = BTreeMap::new();
+
+    for tag in COPIED_TABLES {
+        if let Some(data) = raw_table(face, tag) {
+            tables.insert(*tag, data.to_vec());
+        }
+    }
+
+    let (glyf, loca) = subset_glyf(face, &keep);
+    tables.insert(*b"glyf", glyf);
+    tables.insert(*b"loca", loca);
+
+    let (hhea, hmtx) = subset_hmtx(face, &keep);
+    tables.insert(*b"hhea", hhea);
+    tables.insert(*b"hmtx", hmtx);
+
+    tables.insert(*b"cmap", build_cmap(face, glyphs));
+    tables.insert(
+        *b"maxp",
+        raw_table(face, b"maxp").expect("no maxp found").to_vec(),
+    );
+    tables.insert(*b"post", build_post(face));
+
+    let mut head = raw_table(face, b"head").expect("no head found").to_vec();
+    // checkSumAdjustment is set once the font is written
+    head[8..12].copy_from_slice(&[0; 4]);
+    // always write a long `loca`
+    head[50..52].copy_from_slice(&1u16.to_be_bytes());
+    tables.insert(*b"head", head);
+
+    write_font(tables)
+}
+
+/// Create a six letter subset tag for a set of glyphs.
+///
+/// The tag is a 64 bit FNV-1a hash of the sorted glyph IDs, so the same subset
+/// always gets the same tag whichever order the glyphs are given in and
+/// whichever version of Rust built it. See section 9.6.4 of the PDF spec.
+pub(crate) fn subset_tag<'a, I: IntoIterator<Item = &'a u16>>(glyphs: I) -> String {
+    let glyphs: BTreeSet<u16> = glyphs.into_iter().copied().collect();
+    let mut hash = glyphs
+        .iter()
+        .flat_map(|glyph| glyph.to_be_bytes())
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    (0..6)
+        .map(|_| {
+            let c = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            c
+        })
+        .collect()
+}
+
+pub(crate) fn raw_table<'a>(face: &Face<'a>, tag: &[u8; 4]) -> Option<&'a [u8]> {
+    face.raw_face().table(Tag::from_bytes(tag))
+}
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Get the raw `glyf` data for a glyph.
+fn glyph_data<'a>(face: &Face<'a>, glyph_id: u16) -> Option<&'a [u8]> {
+    let loca = raw_table(face, b"loca")?;
+    let glyf = raw_table(face, b"glyf")?;
+    let i = glyph_id as usize;
+    let (start, end) = match face.tables().head.index_to_location_format {
+        IndexToLocationFormat::Short if loca.len() >= i * 2 + 4 => (
+            read_u16(loca, i * 2) as usize * 2,
+            read_u16(loca, i * 2 + 2) as usize * 2,
+        ),
+        IndexToLocationFormat::Long if loca.len() >= i * 4 + 8 => (
+            read_u32(loca, i * 4) as usize,
+            read_u32(loca, i * 4 + 4) as usize,
+        ),
+        _ => return None,
+    };
+    glyf.get(start..end)
+}
+
+/// Get the glyphs referenced by a composite glyph.
+fn glyph_components(data: &[u8]) -> Vec<u16> {
+    let mut components = Vec::new();
+    if data.len() < 10 || (read_u16(data, 0) as i16) >= 0 {
+        return components;
+    }
+
+    let mut offset = 10;
+    while offset + 4 <= data.len() {
+        let flags = read_u16(data, offset);
+        components.push(read_u16(data, offset + 2));
+        offset += 4;
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+    components
+}
+
+/// Find all the glyphs needed to render a set of glyphs.
+fn glyph_closure<I: IntoIterator<Item = u16>>(face: &Face, glyphs: I) -> BTreeSet<u16> {
+    let mut keep = BTreeSet::new();
+    let mut pending: Vec<u16> = glyphs.into_iter().collect();
+    pending.push(0);
+    while let Some(glyph_id) = pending.pop() {
+        if glyph_id >= face.number_of_glyphs() || !keep.insert(glyph_id) {
+            continue;
+        }
+        if let Some(data) = glyph_data(face, glyph_id) {
+            pending.extend(
+                glyph_components(data)
+                    .into_iter()
+                    .filter(|c| !keep.contains(c)),
+            );
+        }
+    }
+    keep
+}
+
+/// Build new `glyf` and long format `loca` tables containing only the kept glyphs.
+fn subset_glyf(face: &Face, keep: &BTreeSet<u16>) -> (Vec<u8>, Vec<u8>) {
+    let mut glyf = Vec::new();
+    let mut loca = Vec::new();
+    for glyph_id in 0..face.number_of_glyphs() {
+        loca.extend((glyf.len() as u32).to_be_bytes());
+        if keep.contains(&glyph_id) {
+            if let Some(data) = glyph_data(face, glyph_id) {
+                glyf.extend(data);
+                pad(&mut glyf);
+            }
+        }
+    }
+    loca.extend((glyf.len() as u32).to_be_bytes());
+    (glyf, loca)
+}
+
+/// Build new `hhea` and `hmtx` tables.
+///
+/// Metrics are only written up to the last kept glyph, the remaining glyphs
+/// share its advance width as allowed by the spec.
+fn subset_hmtx(face: &Face, keep: &BTreeSet<u16>) -> (Vec<u8>, Vec<u8>) {
+    let last = keep.last().copied().unwrap_or(0);
+    let mut hmtx = Vec::new();
+    for glyph_id in 0..=last {
+        let (advance, lsb) = if keep.contains(&glyph_id) {
+            let glyph = owned_ttf_parser::GlyphId(glyph_id);
+            (
+                face.glyph_hor_advance(glyph).unwrap_or(0),
+                face.glyph_hor_side_bearing(glyph).unwrap_or(0),
+            )
+        } else {
+            (0, 0)
+        };
+        hmtx.extend(advance.to_be_bytes());
+        hmtx.extend(lsb.to_be_bytes());
+    }
+    for _ in last + 1..face.number_of_glyphs() {
+        hmtx.extend(0i16.to_be_bytes());
+    }
+
+    let mut hhea = raw_table(face, b"hhea").expect("no hhea found").to_vec();
+    hhea[34..36].copy_from_slice(&(last + 1).to_be_bytes());
+    (hhea, hmtx)
+}
+
+/// Build a `cmap` table mapping characters to the used glyphs.
+///
+/// Every character that the font maps to a used glyph is kept. A format 4
+/// subtable is written when it fits, characters outside the basic multilingual
+/// plane, or too many runs for format 4, are added with a format 12 subtable.
+fn build_cmap(face: &Face, glyphs: &BTreeSet<u16>) -> Vec<u8> {
+    let mut chars: BTreeMap<u32, u16> = BTreeMap::new();
+    if let Some(cmap) = face.tables().cmap {
+        for subtable in cmap.subtables.into_iter().filter(|s| s.is_unicode()) {
+            subtable.codepoints(|c| {
+                if let Some(gid) = subtable
+                    .glyph_index(c)
+                    .filter(|gid| glyphs.contains(&gid.0))
+                {
+                    chars.entry(c).or_insert(gid.0);
+                }
+            });
+        }
+    }
+
+    cmap_from_chars(&chars)
+}
+
+/// Encode a `cmap` table with Windows Unicode subtables for the characters.
+fn cmap_from_chars(chars: &BTreeMap<u32, u16>) -> Vec<u8> {
+    let mut subtables = Vec::new();
+    let format_4 = cmap_format_4(chars);
+    let needs_format_12 = format_4.is_none() || chars.keys().any(|&c| c > 0xFFFF);
+    if let Some(data) = format_4 {
+        subtables.push((1u16, data));
+    }
+    if needs_format_12 {
+        subtables.push((10, cmap_format_12(chars)));
+    }
+
+    let mut cmap = Vec::new();
+    cmap.extend(0u16.to_be_bytes());
+    cmap.extend((subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + 8 * subtables.len() as u32;
+    for (encoding_id, data) in subtables.iter() {
+        cmap.extend(3u16.to_be_bytes());
+        cmap.extend(encoding_id.to_be_bytes());
+        cmap.extend(offset.to_be_bytes());
+        offset += data.len() as u32;
+    }
+    for (_, data) in subtables {
+        cmap.extend(data);
+    }
+    cmap
+}
+
+/// Group characters into runs where both the character and glyph increase by one.
+fn cmap_runs<I: Iterator<Item = (u32, u16)>>(chars: I) -> Vec<(u32, u32, u16)> {
+    let mut runs: Vec<(u32, u32, u16)> = Vec::new();
+    for (c, glyph_id) in chars {
+        match runs.last_mut() {
+            Some((start, end, start_glyph))
+                if *end + 1 == c && (*start_glyph as u32 + c - *start) == glyph_id as u32 =>
+            {
+                *end = c
+            }
+            _ => runs.push((c, c, glyph_id)),
+        }
+    }
+    runs
+}
+
+/// Build a format 4 subtable, or `None` if its length does not fit in 16 bits.
+fn cmap_format_4(chars: &BTreeMap<u32, u16>) -> Option<Vec<u8>> {
+    let mut runs = cmap_runs(
+        chars
+            .iter()
+            .filter(|(&c, _)| c < 0xFFFF)
+            .map(|(&c, &g)| (c, g)),
+    );
+    // the last segment must map 0xFFFF to .notdef, an idDelta of 1
+    runs.push((0xFFFF, 0xFFFF, 0));
+
+    let seg_count = runs.len() as u32;
+    let length = u16::try_from(16 + 8 * seg_count).ok()?;
+    let search_range = 2 * (1u32 << (31 - seg_count.leading_zeros()));
+    let entry_selector = search_range.trailing_zeros() - 1;
+
+    let mut data = Vec::new();
+    data.extend(4u16.to_be_bytes());
+    data.extend(length.to_be_bytes());
+    data.extend(0u16.to_be_bytes());
+    data.extend((seg_count as u16 * 2).to_be_bytes());
+    data.extend((search_range as u16).to_be_bytes());
+    data.extend((entry_selector as u16).to_be_bytes());
+    data.extend(((seg_count * 2 - search_range) as u16).to_be_bytes());
+    for &(_, end, _) in runs.iter() {
+        data.extend((end as u16).to_be_bytes());
+    }
+    data.extend(0u16.to_be_bytes());
+    for &(start, _, _) in runs.iter() {
+        data.extend((start as u16).to_be_bytes());
+    }
+    for &(start, _, glyph_id) in runs.iter() {
+        data.extend(glyph_id.wrapping_sub(start as u16).to_be_bytes());
+    }
+    for _ in runs.iter() {
+        data.extend(0u16.to_be_bytes());
+    }
+    Some(data)
+}
+
+fn cmap_format_12(chars: &BTreeMap<u32, u16>) -> Vec<u8> {
+    let runs = cmap_runs(chars.iter().map(|(&c, &g)| (c, g)));
+
+    let mut data = Vec::new();
+    data.extend(12u16.to_be_bytes());
+    data.extend(0u16.to_be_bytes());
+    data.extend((16 + 12 * runs.len() as u32).to_be_bytes());
+    data.extend(0u32.to_be_bytes());
+    data.extend((runs.len() as u32).to_be_bytes());
+    for (start, end, glyph_id) in runs {
+        data.extend(start.to_be_bytes());
+        data.extend(end.to_be_bytes());
+        data.extend((glyph_id as u32).to_be_bytes());
+    }
+    data
+}
+
+/// Build a version 3 `post` table, which has no glyph names.
+fn build_post(face: &Face) -> Vec<u8> {
+    let mut post = raw_table(face, b"post")
+        .map(|data| data[..32.min(data.len())].to_vec())
+        .unwrap_or_default();
+    post.resize(32, 0);
+    post[0..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+    post
+}
+
+fn pad(data: &mut Vec<u8>) {
+    while !data.len().is_multiple_of(4) {
+        data.push(0);
+    }
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Write tables out as a font file.
+///
+/// The sfnt version is `OTTO` if there is a `CFF ` or `CFF2` table, as
+/// readers expect CFF outlines in an OpenType font, and TrueType otherwise.
+pub(crate) fn write_font(tables: BTreeMap<[u8; 4], Vec<u8>>) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let search_range = 16 * (1u16 << (15 - num_tables.leading_zeros()));
+    let entry_selector = (search_range / 16).trailing_zeros() as u16;
+
+    let mut font = Vec::new();
+    if tables.contains_key(b"CFF ") || tables.contains_key(b"CFF2") {
+        font.extend(b"OTTO");
+    } else {
+        font.extend(0x0001_0000u32.to_be_bytes());
+    }
+    font.extend(num_tables.to_be_bytes());
+    font.extend(search_range.to_be_bytes());
+    font.extend(entry_selector.to_be_bytes());
+    font.extend((num_tables * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for (tag, data) in tables.iter() {
+        if tag == b"head" {
+            head_offset = Some(offset);
+        }
+        font.extend(tag);
+        font.extend(checksum(data).to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((data.len() as u32).to_be_bytes());
+        offset += (data.len() + 3) & !3;
+    }
+    for data in tables.values() {
+        font.extend(data);
+        pad(&mut font);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+        font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn georgia() -> Vec<u8> {
+        std::fs::read("assets/Georgia.ttf").unwrap()
+    }
+
+    #[test]
+    fn subset_keeps_used_glyphs() {
+        let data = georgia();
+        let face = Face::parse(&data, 0).unwrap();
+        let glyphs: BTreeMap<u16, char> = "PDF"
+            .chars()
+            .map(|c| (face.glyph_index(c).unwrap().0, c))
+            .collect();
+
+        let subset_data = subset(&face, &glyphs.keys().copied().collect());
+        let subset_face = Face::parse(&subset_data, 0).unwrap();
+
+        assert!(subset_data.len() < data.len() / 10);
+        assert_eq!(subset_face.number_of_glyphs(), face.number_of_glyphs());
+        for (&glyph_id, &c) in glyphs.iter() {
+            let glyph_id = owned_ttf_parser::GlyphId(glyph_id);
+            assert_eq!(subset_face.glyph_index(c), Some(glyph_id));
+            assert_eq!(
+                subset_face.glyph_hor_advance(glyph_id),
+                face.glyph_hor_advance(glyph_id)
+            );
+            assert_eq!(
+                subset_face.glyph_bounding_box(glyph_id),
+                face.glyph_bounding_box(glyph_id)
+            );
+        }
+        let unused = face.glyph_index('x').unwrap();
+        assert_eq!(subset_face.glyph_bounding_box(unused), None);
+    }
+
+    #[test]
+    fn cmap_format_4_ends_with_notdef() {
+        let chars = BTreeMap::from([(0x41, 3), (0x42, 4), (0x20AC, 9)]);
+        let data = cmap_format_4(&chars).unwrap();
+        let seg_count = read_u16(&data, 6) as usize / 2;
+        assert_eq!(seg_count, 3);
+        let end_codes = 14;
+        let id_deltas = end_codes + 2 * seg_count + 2 + 2 * seg_count;
+        assert_eq!(read_u16(&data, end_codes + 2 * (seg_count - 1)), 0xFFFF);
+        assert_eq!(read_u16(&data, id_deltas + 2 * (seg_count - 1)), 1);
+        let subtable = owned_ttf_parser::cmap::Subtable4::parse(&data).unwrap();
+        assert_eq!(
+            subtable.glyph_index(0x42),
+            Some(owned_ttf_parser::GlyphId(4))
+        );
+        assert_eq!(
+            subtable.glyph_index(0x20AC),
+            Some(owned_ttf_parser::GlyphId(9))
+        );
+        assert_eq!(
+            subtable.glyph_index(0xFFFF),
+            Some(owned_ttf_parser::GlyphId(0))
+        );
+    }
+
+    #[test]
+    fn cmap_falls_back_to_format_12_when_format_4_is_too_long() {
+        // every other character is mapped, so each one needs its own segment
+        let chars: BTreeMap<u32, u16> = (0..9000u32).map(|i| (i * 2, i as u16 + 1)).collect();
+        assert!(cmap_format_4(&chars).is_none());
+
+        let cmap = cmap_from_chars(&chars);
+        assert_eq!(read_u16(&cmap, 2), 1);
+        assert_eq!(read_u16(&cmap, 6), 10);
+        assert_eq!(read_u16(&cmap, 12), 12);
+    }
+
+    #[test]
+    fn subset_tag_is_six_capitals() {
+        let tag = subset_tag(&[0, 1, 2]);
+        assert_eq!(tag.len(), 6);
+        assert!(tag.chars().all(|c| c.is_ascii_uppercase()));
+        // the tag is stable across runs, builds and glyph order
+        assert_eq!(tag, "AYBVJI");
+        assert_eq!(tag, subset_tag(&[2, 0, 1]));
+    }
+}
@@ -0,0 +1,180 @@
+//! `ToUnicode` CMaps
+//!
+//! Maps the codes that were shown back to the text they represent so that
+//! text can be extracted and searched. Runs of codes that map to consecutive
+//! characters are written as a `bfrange`, the rest as a `bfchar`. Text is
+//! written as UTF-16BE, so characters outside the basic multilingual plane
+//! become surrogate pairs and ligatures map to several characters. See section
+//! 9.10.3 of the PDF spec.
+use std::collections::BTreeMap;
+
+/// The most mappings allowed between a `begin` and `end` operator.
+const MAX_BLOCK_LENGTH: usize = 100;
+
+/// A run of glyphs that map to text ending in consecutive code units.
+struct Range {
+    start: u16,
+    end: u16,
+    text: Vec<u16>,
+}
+
+impl Range {
+    /// Extend the range with a glyph if it continues the run.
+    ///
+    /// A range can not cross a change in the high byte of the glyph ID, or
+    /// overflow the low byte of the last code unit of the text.
+    fn extend(&mut self, glyph_id: u16, text: &[u16]) -> bool {
+        let (prefix, last) = self.text.split_at(self.text.len() - 1);
+        let offset = glyph_id.wrapping_sub(self.start);
+        let fits = self.end.checked_add(1) == Some(glyph_id)
+            && glyph_id >> 8 == self.start >> 8
+            && (last[0] & 0xFF) + offset <= 0xFF
+            && text.len() == self.text.len()
+            && text[..prefix.len()] == *prefix
+            && text[prefix.len()] == last[0] + offset;
+        if fits {
+            self.end = glyph_id;
+        }
+        fits
+    }
+}
+
+/// Create a `ToUnicode` CMap for the codes used in a font.
+///
+/// Codes are `code_bytes` long, two for the glyph IDs of Type0 fonts and one
+/// for simple fonts.
+pub(crate) fn create_cmap(name: &str, code_bytes: usize, codes: &BTreeMap<u16, String>) -> String {
+    let (low, high) = (
+        format!("{:0width$X}", 0, width = code_bytes * 2),
+        format!(
+            "{:0width$X}",
+            (1u32 << (code_bytes * 8)) - 1,
+            width = code_bytes * 2
+        ),
+    );
+    format!(
+        "/CIDInit /ProcSet findresource begin\n\
+         12 dict begin\n\
+         begincmap\n\
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+         /CMapName /{name}-UCS def\n\
+         /CMapType 2 def\n\
+         1 begincodespacerange\n\
+         <{low}> <{high}>\n\
+         endcodespacerange\n\
+         {mappings}\
+         endcmap\n\
+         CMapName currentdict /CMap defineresource pop\n\
+         end\n\
+         end\n",
+        mappings = create_mappings(code_bytes, codes)
+    )
+}
+
+/// Create the mappings of a `ToUnicode` CMap.
+///
+/// Codes that map to no text, such as the extra glyphs of a decomposed
+/// character, are left out.
+fn create_mappings(code_bytes: usize, glyphs: &BTreeMap<u16, String>) -> String {
+    let width = code_bytes * 2;
+    let mut ranges: Vec<Range> = Vec::new();
+    for (&glyph_id, text) in glyphs.iter().filter(|(_, text)| !text.is_empty()) {
+        let text: Vec<u16> = text.encode_utf16().collect();
+        if !ranges
+            .last_mut()
+            .is_some_and(|range| range.extend(glyph_id, &text))
+        {
+            ranges.push(Range {
+                start: glyph_id,
+                end: glyph_id,
+                text,
+            });
+        }
+    }
+
+    let (chars, ranges): (Vec<Range>, Vec<Range>) = ranges
+        .into_iter()
+        .partition(|range| range.start == range.end);
+    let mut cmap = String::new();
+    for block in chars.chunks(MAX_BLOCK_LENGTH) {
+        cmap.push_str(&format!("{} beginbfchar\n", block.len()));
+        for range in block {
+            cmap.push_str(&format!(
+                "<{:0width$X}> <{}>\n",
+                range.start,
+                hex(&range.text)
+            ));
+        }
+        cmap.push_str("endbfchar\n");
+    }
+    for block in ranges.chunks(MAX_BLOCK_LENGTH) {
+        cmap.push_str(&format!("{} beginbfrange\n", block.len()));
+        for range in block {
+            cmap.push_str(&format!(
+                "<{:0width$X}> <{:0width$X}> <{}>\n",
+                range.start,
+                range.end,
+                hex(&range.text)
+            ));
+        }
+        cmap.push_str("endbfrange\n");
+    }
+    cmap
+}
+
+fn hex(text: &[u16]) -> String {
+    text.iter().map(|unit| format!("{:04X}", unit)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mappings(glyphs: &[(u16, &str)]) -> String {
+        create_mappings(
+            2,
+            &glyphs
+                .iter()
+                .map(|&(glyph_id, text)| (glyph_id, text.to_owned()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn consecutive_glyphs_use_ranges() {
+        let cmap = mappings(&[(3, "A"), (4, "B"), (5, "C"), (6, "E"), (7, "F"), (9, "x")]);
+        assert_eq!(
+            cmap,
+            "1 beginbfchar\n<0009> <0078>\nendbfchar\n\
+             2 beginbfrange\n<0003> <0005> <0041>\n<0006> <0007> <0045>\nendbfrange\n"
+        );
+    }
+
+    #[test]
+    fn ranges_do_not_cross_byte_boundaries() {
+        let cmap = mappings(&[
+            (0xFE, "a"),
+            (0xFF, "b"),
+            (0x100, "c"),
+            (0x101, "\u{FF}"),
+            (0x102, "\u{100}"),
+        ]);
+        assert!(cmap.contains("<00FE> <00FF> <0061>\n"));
+        assert!(cmap.contains("<0100> <0063>\n<0101> <00FF>\n<0102> <0100>\n"));
+    }
+
+    #[test]
+    fn single_byte_codes() {
+        let cmap = create_cmap("Icons", 1, &[(0x41, "■".to_owned())].into());
+        assert!(cmap.contains("<00> <FF>\nendcodespacerange\n"));
+        assert!(cmap.contains("1 beginbfchar\n<41> <25A0>\nendbfchar\n"));
+    }
+
+    #[test]
+    fn multiple_code_units() {
+        let cmap = mappings(&[(1, "fi"), (2, "ffi"), (3, "😀"), (4, "😁"), (5, "")]);
+        assert!(cmap.contains("<0001> <00660069>\n<0002> <006600660069>\n"));
+        assert!(cmap.contains("<0003> <0004> <D83DDE00>\n"));
+        assert!(!cmap.contains("<0005>"));
+    }
+}
@@ -0,0 +1,137 @@
+//! Vertical writing metrics
+//!
+//! Fonts written with the `Identity-V` encoding are positioned with the `W2`
+//! and `DW2` entries of the CIDFont instead of the `W` array. Each glyph has a
+//! vertical displacement and a position vector from its horizontal origin to
+//! its vertical origin. See section 9.7.4.3 of the PDF spec.
+//!
+//! The metrics are read from the `vmtx` and `VORG` tables. Fonts without them
+//! fall back to the ascender and descender, as recommended by the OpenType
+//! spec for the `vhea` table.
+use std::collections::BTreeMap;
+
+use lopdf::Object;
+use owned_ttf_parser::{Face, GlyphId};
+
+/// The vertical advance of a glyph, in font units.
+pub(crate) fn advance(face: &Face, glyph_id: u16) -> i32 {
+    face.glyph_ver_advance(GlyphId(glyph_id)).map_or_else(
+        || face.ascender() as i32 - face.descender() as i32,
+        i32::from,
+    )
+}
+
+/// The height of the vertical origin above the baseline, in font units.
+fn origin(face: &Face, glyph_id: u16) -> i32 {
+    let gid = GlyphId(glyph_id);
+    if let Some(y) = face.glyph_y_origin(gid) {
+        return y as i32;
+    }
+    match (
+        face.glyph_bounding_box(gid),
+        face.glyph_ver_side_bearing(gid),
+    ) {
+        (Some(bbox), Some(bearing)) => bbox.y_max as i32 + bearing as i32,
+        _ => face.ascender() as i32,
+    }
+}
+
+/// The vertical origin and displacement of a glyph, in glyph space.
+fn metrics(face: &Face, glyph_id: u16) -> (i64, i64) {
+    let scale = |v: i32| (v as f32 * 1000.0 / face.units_per_em() as f32).round() as i64;
+    (
+        scale(origin(face, glyph_id)),
+        -scale(advance(face, glyph_id)),
+    )
+}
+
+/// Create the `DW2` and `W2` entries for the given glyphs.
+///
+/// The most common metrics are used as the default so that `W2` only lists
+/// the glyphs that differ, such as rotated punctuation.
+pub(crate) fn create_metrics<I: IntoIterator<Item = u16>>(
+    face: &Face,
+    glyph_ids: I,
+) -> (Vec<Object>, Vec<Object>) {
+    let glyphs: Vec<(u16, (i64, i64))> = glyph_ids
+        .into_iter()
+        .map(|gid| (gid, metrics(face, gid)))
+        .collect();
+
+    let mut counts = BTreeMap::new();
+    for (_, m) in glyphs.iter() {
+        *counts.entry(*m).or_insert(0) += 1;
+    }
+    let default = counts
+        .into_iter()
+        .max_by_key(|&(_, count)| count)
+        .map_or((880, -1000), |(m, _)| m);
+
+    let scale = 1000.0 / face.units_per_em() as f32;
+    let mut list = Vec::new();
+    let mut run: Vec<Object> = Vec::new();
+    let mut next_gid = None;
+    for (gid, (origin, displacement)) in glyphs {
+        if (origin, displacement) == default {
+            continue;
+        }
+        if next_gid != Some(gid) && !run.is_empty() {
+            list.push(Object::Array(std::mem::take(&mut run)));
+        }
+        if run.is_empty() {
+            list.push(Object::Integer(gid as i64));
+        }
+        // the position vector is horizontally centred on the glyph
+        let half_width = face.glyph_hor_advance(GlyphId(gid)).unwrap_or(0) as f32 * scale / 2.;
+        run.extend([
+            Object::Integer(displacement),
+            Object::Integer(half_width.round() as i64),
+            Object::Integer(origin),
+        ]);
+        next_gid = Some(gid + 1);
+    }
+    if !run.is_empty() {
+        list.push(Object::Array(run));
+    }
+
+    let (origin, displacement) = default;
+    (
+        vec![Object::Integer(origin), Object::Integer(displacement)],
+        list,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_without_vmtx() {
+        let data = std::fs::read("assets/Georgia.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        let glyphs = "Hello".chars().map(|c| face.glyph_index(c).unwrap().0);
+
+        let (default, list) = create_metrics(&face, glyphs);
+        // ascender 1878 and descender -449 at 2048 units per em
+        let default: Vec<i64> = default.iter().map(|o| o.as_i64().unwrap()).collect();
+        assert_eq!(default, vec![917, -1136]);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn metrics_from_vmtx() {
+        let data = std::fs::read("assets/NotoColorEmoji-Partial.ttf").unwrap();
+        let face = Face::parse(&data, 0).unwrap();
+        assert!(face.tables().vmtx.is_some());
+
+        let (default, list) = create_metrics(&face, 0..face.number_of_glyphs());
+        // most glyphs advance 2500 at 2048 units per em
+        assert_eq!(default[1].as_i64().unwrap(), -1221);
+        // the zero width glyph 1 is listed as its CID followed by triples of
+        // displacement and position vector
+        assert_eq!(list[0].as_i64().unwrap(), 1);
+        let first = list[1].as_array().unwrap();
+        assert_eq!(first[0].as_i64().unwrap(), 0);
+        assert_eq!(first.len() % 3, 0);
+    }
+}
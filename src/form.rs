@@ -0,0 +1,250 @@
+//! Form XObjects for reusable content
+//!
+//! A form is a self-contained content stream with its own resources, painted
+//! with `Do` like an image. The same form can be painted many times, and
+//! because its resource names are looked up in its own resources they can
+//! not clash with the names used by the content that paints it. This makes
+//! forms the way to embed a page of another document. See section 8.10 of the
+//! PDF spec.
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::document::{ContentBuilder, Resources};
+use crate::import::{page_attribute, ObjectCopier};
+
+/// A form XObject, painted with [ContentBuilder::add_xobject] once it has been
+/// added to the resources with [Resources::set_xobject].
+pub(crate) struct FormBuilder {
+    bbox: [f32; 4],
+    matrix: Option<[f32; 6]>,
+    group: Option<String>,
+    content: Vec<u8>,
+    resources: Object,
+}
+
+impl FormBuilder {
+    /// A form drawn by a closure, using names from `resources`.
+    ///
+    /// The resources are written to the document with the form, so fonts
+    /// still need [Resources::finish] once all content has been built.
+    pub fn new<F>(doc: &mut Document, bbox: [f32; 4], resources: &Resources, draw: F) -> Self
+    where
+        F: FnOnce(ContentBuilder) -> ContentBuilder,
+    {
+        let content = draw(ContentBuilder::new(resources))
+            .build_content()
+            .encode()
+            .expect("could not encode form content");
+        Self {
+            bbox,
+            matrix: None,
+            group: None,
+            content,
+            resources: resources.add_to_doc(doc).into(),
+        }
+    }
+
+    /// A form showing a page of another document.
+    ///
+    /// The page's resources, including any inherited from its page tree, are
+    /// copied along with everything they refer to. The bounding box is the
+    /// page's crop box, or its media box if it has none.
+    ///
+    /// Each call copies the resources again, so use
+    /// [crate::import::PageImporter::form] for several pages of the same
+    /// document.
+    pub fn from_page(doc: &mut Document, source: &Document, page_id: ObjectId) -> Self {
+        let mut copier = ObjectCopier::new(source);
+        let form = Self::from_copied_page(doc, &mut copier, page_id);
+        copier.finish(doc);
+        form
+    }
+
+    /// A form showing a page, copying its resources with `copier` so that
+    /// objects already copied are shared.
+    pub(crate) fn from_copied_page(
+        doc: &mut Document,
+        copier: &mut ObjectCopier,
+        page_id: ObjectId,
+    ) -> Self {
+        let source = copier.source();
+        let content = source
+            .get_page_content(page_id)
+            .unwrap_or_else(|e| panic!("Could not read content of page {:?}: {}", page_id, e));
+        let bbox = page_attribute(source, page_id, b"CropBox")
+            .or_else(|| page_attribute(source, page_id, b"MediaBox"))
+            .and_then(|o| source.dereference(o).ok())
+            .and_then(|(_, o)| o.as_array().ok())
+            .and_then(|a| rectangle(a))
+            .unwrap_or_else(|| panic!("Page {:?} has no valid MediaBox", page_id));
+        let resources = match page_attribute(source, page_id, b"Resources") {
+            Some(resources) => copier.copy(doc, resources),
+            None => Dictionary::new().into(),
+        };
+        Self {
+            bbox,
+            matrix: None,
+            group: None,
+            content,
+            resources,
+        }
+    }
+
+    /// Transform form space into the space it is painted in (`Matrix`).
+    pub fn matrix(mut self, matrix: [f32; 6]) -> Self {
+        self.matrix = Some(matrix);
+        self
+    }
+
+    /// Make the form a transparency group composited in a device colour
+    /// space (`Group`), such as `DeviceGray`.
+    ///
+    /// Soft masks must be drawn by a transparency group, see
+    /// [crate::graphics_state::SoftMask]. See section 11.6.6 of the PDF spec.
+    pub fn transparency_group(mut self, colour_space: &str) -> Self {
+        self.group = Some(colour_space.to_owned());
+        self
+    }
+
+    pub fn add_to_doc(self, doc: &mut Document) -> ObjectId {
+        let mut dict = dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => self.bbox.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+            "Resources" => self.resources,
+        };
+        if let Some(matrix) = self.matrix {
+            dict.set(
+                "Matrix",
+                matrix.iter().map(|&v| v.into()).collect::<Vec<Object>>(),
+            );
+        }
+        if let Some(colour_space) = self.group {
+            dict.set(
+                "Group",
+                dictionary! {
+                    "Type" => "Group",
+                    "S" => "Transparency",
+                    "CS" => colour_space,
+                },
+            );
+        }
+        doc.add_object(Stream::new(dict, self.content).with_compression(true))
+    }
+}
+
+/// A rectangle as `[llx lly urx ury]`.
+fn rectangle(array: &[Object]) -> Option<[f32; 4]> {
+    match array {
+        [a, b, c, d] => Some([
+            a.as_float().ok()?,
+            b.as_float().ok()?,
+            c.as_float().ok()?,
+            d.as_float().ok()?,
+        ]),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::content::{Content, Operation};
+
+    use super::*;
+
+    fn source_document() -> (Document, ObjectId) {
+        let mut source = Document::with_version("1.7");
+        let pages_id = source.new_object_id();
+        let font_id = source.add_object(dictionary! { "Type" => "Font" });
+        let content = Content {
+            operations: vec![Operation::new("Tf", vec!["F1".into(), 12.into()])],
+        };
+        let content_id = source.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = source.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        source.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }
+            .into(),
+        );
+        (source, page_id)
+    }
+
+    #[test]
+    fn pages_keep_their_resources() {
+        let (source, page_id) = source_document();
+        let mut doc = Document::with_version("1.7");
+        let form_id = FormBuilder::from_page(&mut doc, &source, page_id).add_to_doc(&mut doc);
+
+        let form = doc.get_object(form_id).unwrap().as_stream().unwrap();
+        assert_eq!(
+            form.dict.get(b"Subtype").unwrap().as_name_str().unwrap(),
+            "Form"
+        );
+        let bbox = form.dict.get(b"BBox").unwrap().as_array().unwrap();
+        assert_eq!(bbox[3].as_float().unwrap(), 792.);
+        assert_eq!(form.content, b"/F1 12 Tf");
+
+        // the font is copied into this document, not left pointing at the source
+        let resources = form.dict.get(b"Resources").unwrap().as_dict().unwrap();
+        let fonts = resources.get(b"Font").unwrap().as_dict().unwrap();
+        let font_id = fonts.get(b"F1").unwrap().as_reference().unwrap();
+        let font = doc.get_dictionary(font_id).unwrap();
+        assert_eq!(font.get(b"Type").unwrap().as_name_str().unwrap(), "Font");
+    }
+
+    #[test]
+    fn content_uses_its_own_resources() {
+        let mut doc = Document::with_version("1.7");
+        let mut resources = Resources::default();
+        resources.set_xobject("Im1", (9, 0));
+        let form_id = FormBuilder::new(&mut doc, [0., 0., 10., 10.], &resources, |b| {
+            b.add_xobject("Im1")
+        })
+        .matrix([2., 0., 0., 2., 0., 0.])
+        .add_to_doc(&mut doc);
+
+        let form = doc.get_object(form_id).unwrap().as_stream().unwrap();
+        assert_eq!(form.content, b"/Im1 Do");
+        let resources_id = form.dict.get(b"Resources").unwrap().as_reference().unwrap();
+        let resources = doc.get_dictionary(resources_id).unwrap();
+        assert!(resources
+            .get(b"XObject")
+            .unwrap()
+            .as_dict()
+            .unwrap()
+            .has(b"Im1"));
+        assert_eq!(
+            form.dict.get(b"Matrix").unwrap().as_array().unwrap().len(),
+            6
+        );
+        assert!(!form.dict.has(b"Group"));
+    }
+
+    #[test]
+    fn transparency_groups_have_a_colour_space() {
+        let mut doc = Document::with_version("1.7");
+        let form_id = FormBuilder::new(&mut doc, [0., 0., 1., 1.], &Resources::default(), |b| b)
+            .transparency_group("DeviceGray")
+            .add_to_doc(&mut doc);
+
+        let form = doc.get_object(form_id).unwrap().as_stream().unwrap();
+        let group = form.dict.get(b"Group").unwrap().as_dict().unwrap();
+        assert_eq!(
+            group.get(b"S").unwrap().as_name_str().unwrap(),
+            "Transparency"
+        );
+        assert_eq!(
+            group.get(b"CS").unwrap().as_name_str().unwrap(),
+            "DeviceGray"
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Graphics state parameter dictionaries
+//!
+//! Transparency can only be set through an `ExtGState` dictionary in the
+//! resources, selected in a content stream with `gs`. Graphics states are
+//! added to [crate::document::Resources], which names them, and are then set
+//! with [crate::document::ContentBuilder::graphics_state]. See section 8.4.5
+//! and chapter 11 of the PDF spec.
+use lopdf::{dictionary, Dictionary, Object, ObjectId};
+
+/// How colours are composited with what is already on the page.
+///
+/// See section 11.3.5 of the PDF spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl BlendMode {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Multiply => "Multiply",
+            Self::Screen => "Screen",
+            Self::Overlay => "Overlay",
+            Self::Darken => "Darken",
+            Self::Lighten => "Lighten",
+            Self::ColorDodge => "ColorDodge",
+            Self::ColorBurn => "ColorBurn",
+            Self::HardLight => "HardLight",
+            Self::SoftLight => "SoftLight",
+            Self::Difference => "Difference",
+            Self::Exclusion => "Exclusion",
+            Self::Hue => "Hue",
+            Self::Saturation => "Saturation",
+            Self::Color => "Color",
+            Self::Luminosity => "Luminosity",
+        }
+    }
+}
+
+/// A mask that varies the opacity of everything painted while it is set.
+///
+/// Masks are derived from a transparency group XObject, either from its
+/// luminosity or from its alpha. See section 11.6.5.2 of the PDF spec.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SoftMask {
+    /// Remove a soft mask set by an earlier graphics state.
+    None,
+    Luminosity(ObjectId),
+    Alpha(ObjectId),
+}
+
+/// A set of graphics state parameters, written as an `ExtGState` dictionary.
+///
+/// Parameters that are not set are left as they are when it is selected.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct GraphicsState {
+    fill_alpha: Option<f32>,
+    stroke_alpha: Option<f32>,
+    blend_mode: Option<BlendMode>,
+    soft_mask: Option<SoftMask>,
+}
+
+impl GraphicsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the opacity of fills and text (`ca`), from 0 to 1.
+    pub fn fill_alpha(mut self, alpha: f32) -> Self {
+        self.fill_alpha = Some(check_alpha(alpha));
+        self
+    }
+
+    /// Set the opacity of strokes (`CA`), from 0 to 1.
+    pub fn stroke_alpha(mut self, alpha: f32) -> Self {
+        self.stroke_alpha = Some(check_alpha(alpha));
+        self
+    }
+
+    /// Set the blend mode (`BM`).
+    pub fn blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = Some(blend_mode);
+        self
+    }
+
+    /// Set the soft mask (`SMask`).
+    pub fn soft_mask(mut self, soft_mask: SoftMask) -> Self {
+        self.soft_mask = Some(soft_mask);
+        self
+    }
+
+    pub fn as_dictionary(&self) -> Dictionary {
+        let mut dict = dictionary! { "Type" => "ExtGState" };
+        if let Some(alpha) = self.fill_alpha {
+            dict.set("ca", alpha);
+        }
+        if let Some(alpha) = self.stroke_alpha {
+            dict.set("CA", alpha);
+        }
+        if let Some(blend_mode) = self.blend_mode {
+            dict.set("BM", blend_mode.name());
+        }
+        if let Some(soft_mask) = self.soft_mask {
+            let mask = |subtype: &str, group: ObjectId| -> Object {
+                dictionary! {
+                    "Type" => "Mask",
+                    "S" => subtype,
+                    "G" => group,
+                }
+                .into()
+            };
+            dict.set(
+                "SMask",
+                match soft_mask {
+                    SoftMask::None => "None".into(),
+                    SoftMask::Luminosity(group) => mask("Luminosity", group),
+                    SoftMask::Alpha(group) => mask("Alpha", group),
+                },
+            );
+        }
+        dict
+    }
+}
+
+fn check_alpha(alpha: f32) -> f32 {
+    if !(0. ..=1.).contains(&alpha) {
+        panic!("Alpha must be between 0 and 1 but was {}", alpha);
+    }
+    alpha
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_set_parameters_are_written() {
+        let dict = GraphicsState::new()
+            .fill_alpha(0.5)
+            .blend_mode(BlendMode::Multiply)
+            .soft_mask(SoftMask::Luminosity((7, 0)))
+            .as_dictionary();
+
+        assert_eq!(dict.get(b"ca").unwrap().as_float().unwrap(), 0.5);
+        assert!(dict.get(b"CA").is_err());
+        assert_eq!(dict.get(b"BM").unwrap().as_name_str().unwrap(), "Multiply");
+        let mask = dict.get(b"SMask").unwrap().as_dict().unwrap();
+        assert_eq!(mask.get(b"S").unwrap().as_name_str().unwrap(), "Luminosity");
+        assert_eq!(mask.get(b"G").unwrap().as_reference().unwrap(), (7, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 1")]
+    fn alpha_out_of_range() {
+        GraphicsState::new().stroke_alpha(1.5);
+    }
+}
@@ -0,0 +1,433 @@
+//! Copying objects and pages from other documents
+//!
+//! Object numbers are only meaningful within one document, so objects that
+//! are copied from another document are given new numbers, and references
+//! between them are rewritten to match. Objects that are shared, such as a
+//! font used by several pages, are only copied once.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use crate::form::FormBuilder;
+
+/// Page attributes that pages inherit from their ancestors in the page tree.
+///
+/// See section 7.7.3.4 of the PDF spec.
+const INHERITABLE: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Imports pages from a source document.
+///
+/// Objects are only copied once however many pages use them, so pages
+/// imported with the same importer share their fonts and images. Pages are
+/// imported with [crate::document::DocumentAdditions::import_page] or
+/// [PageImporter::import_page]. Call [PageImporter::finish] once all pages
+/// have been imported.
+pub(crate) struct PageImporter<'a> {
+    pages: BTreeMap<u32, ObjectId>,
+    copier: ObjectCopier<'a>,
+}
+
+impl<'a> PageImporter<'a> {
+    pub fn new(source: &'a Document) -> Self {
+        Self {
+            pages: source.get_pages(),
+            copier: ObjectCopier::new(source),
+        }
+    }
+
+    /// The id in the source document of a page, counting from 1.
+    fn page_id(&self, number: u32) -> ObjectId {
+        *self.pages.get(&number).unwrap_or_else(|| {
+            panic!(
+                "Page {} is out of range, the document has {} pages",
+                number,
+                self.pages.len()
+            )
+        })
+    }
+
+    /// Copy a page, counting from 1, into `doc` as a child of `pages_id`.
+    ///
+    /// Attributes the page inherits in the source document are set on the
+    /// copy, as it will not inherit them from its new parent. The page still
+    /// has to be added to the `Kids` of its parent.
+    pub fn import_page(&mut self, doc: &mut Document, number: u32, pages_id: ObjectId) -> ObjectId {
+        let page_id = self.page_id(number);
+        let source = self.copier.source;
+        let page = source
+            .get_dictionary(page_id)
+            .unwrap_or_else(|e| panic!("Could not read page {}: {}", number, e));
+
+        // map the page before copying it so that its annotations refer to it,
+        // reusing the id given to it by links from pages already imported
+        let new_id = self.copier.reserved_page(page_id).unwrap_or_else(|| {
+            let new_id = doc.new_object_id();
+            self.copier.ids.insert(page_id, new_id);
+            new_id
+        });
+        let mut copy = Dictionary::new();
+        for (key, value) in page.iter() {
+            if key != b"Parent" {
+                copy.set(key.clone(), self.copier.copy(doc, value));
+            }
+        }
+        for key in INHERITABLE {
+            if !page.has(key) {
+                if let Some(value) = page_attribute(source, page_id, key) {
+                    copy.set(key, self.copier.copy(doc, value));
+                }
+            }
+        }
+        copy.set("Parent", pages_id);
+        doc.objects.insert(new_id, copy.into());
+        new_id
+    }
+
+    /// A form showing a page, counting from 1, see [FormBuilder::from_page].
+    pub fn form(&mut self, doc: &mut Document, number: u32) -> FormBuilder {
+        let page_id = self.page_id(number);
+        FormBuilder::from_copied_page(doc, &mut self.copier, page_id)
+    }
+
+    /// Drop references to pages that were never imported, see
+    /// [ObjectCopier::finish].
+    pub fn finish(self, doc: &mut Document) {
+        self.copier.finish(doc);
+    }
+}
+
+/// Copies objects from a source document into another, renumbering them.
+pub(crate) struct ObjectCopier<'a> {
+    source: &'a Document,
+    ids: HashMap<ObjectId, ObjectId>,
+    /// Ids reserved for pages that are referred to but not yet imported.
+    pending_pages: HashSet<ObjectId>,
+}
+
+impl<'a> ObjectCopier<'a> {
+    pub fn new(source: &'a Document) -> Self {
+        Self {
+            source,
+            ids: HashMap::new(),
+            pending_pages: HashSet::new(),
+        }
+    }
+
+    pub fn source(&self) -> &'a Document {
+        self.source
+    }
+
+    /// Copy an object and everything it refers to, returning the copy.
+    ///
+    /// Pages are not copied, so that links between pages do not pull in the
+    /// whole source page tree. References to pages that have not been
+    /// imported yet are given an id that the page takes if it is imported
+    /// later, and become `null` in [ObjectCopier::finish] if it never is.
+    pub fn copy(&mut self, doc: &mut Document, object: &Object) -> Object {
+        match object {
+            Object::Reference(id) if self.is_page_tree_node(*id) => {
+                let new_id = *self.ids.entry(*id).or_insert_with(|| {
+                    let new_id = doc.new_object_id();
+                    self.pending_pages.insert(new_id);
+                    new_id
+                });
+                new_id.into()
+            }
+            Object::Reference(id) => self.copy_id(doc, *id).into(),
+            Object::Array(array) => array
+                .iter()
+                .map(|o| self.copy(doc, o))
+                .collect::<Vec<_>>()
+                .into(),
+            Object::Dictionary(dict) => self.copy_dictionary(doc, dict).into(),
+            Object::Stream(stream) => {
+                let mut stream = stream.clone();
+                stream.dict = self.copy_dictionary(doc, &stream.dict);
+                stream.into()
+            }
+            _ => object.clone(),
+        }
+    }
+
+    /// Copy an indirect object, returning its id in `doc`.
+    ///
+    /// References to objects that are missing from the source are left
+    /// dangling, which readers treat as `null`.
+    pub fn copy_id(&mut self, doc: &mut Document, id: ObjectId) -> ObjectId {
+        if let Some(&new_id) = self.ids.get(&id) {
+            return new_id;
+        }
+        // reserve the id first so that cycles refer back to it
+        let new_id = doc.new_object_id();
+        self.ids.insert(id, new_id);
+        if let Ok(object) = self.source.get_object(id) {
+            let copy = self.copy(doc, object);
+            doc.objects.insert(new_id, copy);
+        }
+        new_id
+    }
+
+    /// The id reserved for a page by a reference to it, if it has not been
+    /// imported yet.
+    fn reserved_page(&mut self, page_id: ObjectId) -> Option<ObjectId> {
+        let new_id = *self.ids.get(&page_id)?;
+        self.pending_pages.remove(&new_id).then_some(new_id)
+    }
+
+    /// Replace references to pages that were never imported with `null`.
+    pub fn finish(self, doc: &mut Document) {
+        if self.pending_pages.is_empty() {
+            return;
+        }
+        for new_id in self.ids.values() {
+            if let Some(object) = doc.objects.get_mut(new_id) {
+                drop_references(object, &self.pending_pages);
+            }
+        }
+    }
+
+    fn is_page_tree_node(&self, id: ObjectId) -> bool {
+        self.source
+            .get_dictionary(id)
+            .and_then(|dict| dict.get(b"Type"))
+            .and_then(Object::as_name_str)
+            .is_ok_and(|name| name == "Page" || name == "Pages")
+    }
+
+    pub fn copy_dictionary(&mut self, doc: &mut Document, dict: &Dictionary) -> Dictionary {
+        let mut copy = Dictionary::new();
+        for (key, value) in dict.iter() {
+            copy.set(key.clone(), self.copy(doc, value));
+        }
+        copy
+    }
+}
+
+fn drop_references(object: &mut Object, ids: &HashSet<ObjectId>) {
+    match object {
+        Object::Reference(id) if ids.contains(id) => *object = Object::Null,
+        Object::Array(array) => array.iter_mut().for_each(|o| drop_references(o, ids)),
+        Object::Dictionary(dict) => dict.iter_mut().for_each(|(_, o)| drop_references(o, ids)),
+        Object::Stream(stream) => stream
+            .dict
+            .iter_mut()
+            .for_each(|(_, o)| drop_references(o, ids)),
+        _ => {}
+    }
+}
+
+/// Look up an attribute of a page, inheriting it from the page tree if the
+/// page does not have it and it is inheritable.
+///
+/// The value may be a reference, so that objects shared between pages stay
+/// shared when they are copied.
+pub(crate) fn page_attribute<'a>(
+    source: &'a Document,
+    page_id: ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut node = source.get_dictionary(page_id).ok()?;
+    loop {
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        if !INHERITABLE.contains(&key) {
+            return None;
+        }
+        let parent = node.get(b"Parent").and_then(Object::as_reference).ok()?;
+        node = source.get_dictionary(parent).ok()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::dictionary;
+
+    use super::*;
+    use crate::document::DocumentAdditions;
+
+    #[test]
+    fn shared_and_cyclic_objects_are_copied_once() {
+        let mut source = Document::with_version("1.7");
+        let font_id = source.add_object(dictionary! { "Type" => "Font" });
+        let a_id = source.new_object_id();
+        let b_id = source.add_object(dictionary! { "Next" => a_id, "Font" => font_id });
+        source.objects.insert(
+            a_id,
+            dictionary! { "Next" => b_id, "Font" => font_id }.into(),
+        );
+
+        let mut doc = Document::with_version("1.7");
+        doc.add_object(dictionary! {});
+        let mut copier = ObjectCopier::new(&source);
+        let a = copier.copy_id(&mut doc, a_id);
+        assert_eq!(doc.objects.len(), 4);
+
+        let a_dict = doc.get_dictionary(a).unwrap();
+        let b = a_dict.get(b"Next").unwrap().as_reference().unwrap();
+        let b_dict = doc.get_dictionary(b).unwrap();
+        assert_eq!(b_dict.get(b"Next").unwrap().as_reference().unwrap(), a);
+        assert_eq!(
+            a_dict.get(b"Font").unwrap().as_reference().unwrap(),
+            b_dict.get(b"Font").unwrap().as_reference().unwrap()
+        );
+    }
+
+    /// Two pages sharing a font, with a link from the first to the second.
+    fn two_page_document() -> Document {
+        let mut source = Document::with_version("1.7");
+        let pages_id = source.new_object_id();
+        let font_id = source.add_object(dictionary! { "Type" => "Font" });
+        let resources_id = source.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+        let second_id = source.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+        });
+        let link = dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Link",
+            "Dest" => vec![second_id.into(), "Fit".into()],
+        };
+        let first_id = source.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Annots" => vec![link.into()],
+            "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+        });
+        source.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![first_id.into(), second_id.into()],
+                "Count" => 2,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 200.into(), 200.into()],
+            }
+            .into(),
+        );
+        let catalog_id = source.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        source.trailer.set("Root", catalog_id);
+        source
+    }
+
+    #[test]
+    fn imported_pages_share_resources() {
+        let source = two_page_document();
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let mut importer = PageImporter::new(&source);
+        let second = importer.import_page(&mut doc, 2, pages_id);
+        let first = doc.import_page(&mut importer, 1, pages_id);
+
+        let first_page = doc.get_dictionary(first).unwrap();
+        let second_page = doc.get_dictionary(second).unwrap();
+        assert_eq!(
+            first_page.get(b"Parent").unwrap().as_reference().unwrap(),
+            pages_id
+        );
+        // inherited resources are set on each page and still shared
+        let resources = first_page
+            .get(b"Resources")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        assert_eq!(
+            second_page
+                .get(b"Resources")
+                .unwrap()
+                .as_reference()
+                .unwrap(),
+            resources
+        );
+        // the page's own media box wins over the inherited one
+        let media_box = first_page.get(b"MediaBox").unwrap().as_array().unwrap();
+        assert_eq!(media_box[2].as_i64().unwrap(), 100);
+        let media_box = second_page.get(b"MediaBox").unwrap().as_array().unwrap();
+        assert_eq!(media_box[2].as_i64().unwrap(), 200);
+
+        // the link points at the imported copy of the second page
+        let annots = first_page.get(b"Annots").unwrap().as_array().unwrap();
+        let dest = annots[0].as_dict().unwrap().get(b"Dest").unwrap();
+        assert_eq!(dest.as_array().unwrap()[0].as_reference().unwrap(), second);
+    }
+
+    #[test]
+    fn links_to_pages_imported_later_are_kept() {
+        let source = two_page_document();
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let mut importer = PageImporter::new(&source);
+        let first = importer.import_page(&mut doc, 1, pages_id);
+        let second = importer.import_page(&mut doc, 2, pages_id);
+        importer.finish(&mut doc);
+
+        let first_page = doc.get_dictionary(first).unwrap();
+        let annots = first_page.get(b"Annots").unwrap().as_array().unwrap();
+        let dest = annots[0].as_dict().unwrap().get(b"Dest").unwrap();
+        assert_eq!(dest.as_array().unwrap()[0].as_reference().unwrap(), second);
+        let second_page = doc.get_dictionary(second).unwrap();
+        assert_eq!(
+            second_page.get(b"Type").unwrap().as_name_str().unwrap(),
+            "Page"
+        );
+    }
+
+    #[test]
+    fn links_to_pages_not_imported_are_dropped() {
+        let source = two_page_document();
+        let mut doc = Document::with_version("1.7");
+        let pages_id = doc.new_object_id();
+        let mut importer = PageImporter::new(&source);
+        let first = importer.import_page(&mut doc, 1, pages_id);
+        importer.finish(&mut doc);
+
+        let first_page = doc.get_dictionary(first).unwrap();
+        let annots = first_page.get(b"Annots").unwrap().as_array().unwrap();
+        let dest = annots[0].as_dict().unwrap().get(b"Dest").unwrap();
+        assert!(matches!(dest.as_array().unwrap()[0], Object::Null));
+        // the source page tree is not copied
+        assert_eq!(doc.objects.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn import_page_out_of_range() {
+        let source = two_page_document();
+        let mut doc = Document::with_version("1.7");
+        PageImporter::new(&source).import_page(&mut doc, 3, (1, 0));
+    }
+
+    #[test]
+    fn page_attributes_are_inherited() {
+        let mut source = Document::with_version("1.7");
+        let pages_id = source.new_object_id();
+        let page_id = source.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Rotate" => 90,
+        });
+        source.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "MediaBox" => vec![0.into(), 0.into(), 100.into(), 200.into()],
+                "Rotate" => 0,
+                "Count" => 1,
+            }
+            .into(),
+        );
+
+        let media_box = page_attribute(&source, page_id, b"MediaBox").unwrap();
+        assert_eq!(media_box.as_array().unwrap().len(), 4);
+        let rotate = page_attribute(&source, page_id, b"Rotate").unwrap();
+        assert_eq!(rotate.as_i64().unwrap(), 90);
+        // only inheritable attributes are looked up in the page tree
+        assert!(page_attribute(&source, page_id, b"Count").is_none());
+    }
+}
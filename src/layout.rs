@@ -0,0 +1,366 @@
+//! Paragraph layout
+//!
+//! Wraps text into lines that fit the width of a box and positions each line
+//! according to an alignment. Text that does not fit in the height of the box
+//! is reported as overflow rather than drawn.
+use std::iter::once;
+
+use lopdf::content::Operation;
+use lopdf::Object;
+
+use crate::fonts::FontReference;
+
+/// How lines of a paragraph are aligned in its box.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Centre,
+    /// Stretch the spaces between words so that lines fill the box.
+    ///
+    /// The last line of a paragraph, or before an explicit line break, is
+    /// left aligned.
+    Justify,
+}
+
+/// A block of text to be wrapped into a box.
+#[derive(Clone, Debug)]
+pub struct Paragraph {
+    text: String,
+    font: String,
+    size: u32,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    leading: Option<f32>,
+    align: Align,
+}
+
+impl Paragraph {
+    /// Create a paragraph in the font with the given key in the font map.
+    ///
+    /// Lines are split on `\n` as well as when they are too wide for the box.
+    pub fn new(text: &str, font: &str, size: u32) -> Self {
+        Self {
+            text: text.to_owned(),
+            font: font.to_owned(),
+            size,
+            x: 0.,
+            y: 0.,
+            width: f32::INFINITY,
+            height: f32::INFINITY,
+            leading: None,
+            align: Align::default(),
+        }
+    }
+
+    /// Set the top left corner of the box.
+    ///
+    /// The first baseline is placed the ascent of the font below the top.
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Set the width to wrap lines at, defaults to no wrapping.
+    pub fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Set the height of the box, defaults to no limit.
+    pub fn height(mut self, height: f32) -> Self {
+        self.height = height;
+        self
+    }
+
+    /// Set the distance between baselines, defaults to 1.2 times the font size.
+    pub fn leading(mut self, leading: f32) -> Self {
+        self.leading = Some(leading);
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn font(&self) -> &str {
+        &self.font
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Break the text into lines and position them in the box.
+    pub fn layout(&self, font: &dyn FontReference) -> TextLayout {
+        let size = self.size as f32;
+        let leading = self.leading.unwrap_or(size * 1.2);
+        let bottom = self.y - self.height;
+        let lines = self.break_lines(font);
+
+        let mut placed = Vec::new();
+        let mut overflow = None;
+        let mut baseline = self.y - font.ascent(size);
+        for (i, line) in lines.iter().enumerate() {
+            if baseline + font.descent(size) < bottom {
+                overflow = Some(self.text[line.start..].to_owned());
+                break;
+            }
+            let last_in_paragraph = lines.get(i + 1).is_none_or(|next| next.hard_break);
+            let gaps = line.words.len().saturating_sub(1);
+            let space = if self.width.is_finite() {
+                self.width - line.width
+            } else {
+                0.
+            };
+            let (offset, word_spacing) = match self.align {
+                Align::Left => (0., 0.),
+                Align::Right => (space, 0.),
+                Align::Centre => (space / 2., 0.),
+                Align::Justify if last_in_paragraph || gaps == 0 => (0., 0.),
+                Align::Justify => (0., space / gaps as f32),
+            };
+            placed.push(PlacedLine {
+                words: line.words.clone(),
+                x: self.x + offset,
+                y: baseline,
+                word_spacing,
+            });
+            baseline -= leading;
+        }
+
+        TextLayout {
+            font: self.font.clone(),
+            size: self.size,
+            lines: placed,
+            overflow,
+        }
+    }
+
+    /// Greedily fill lines with as many words as fit in the width.
+    ///
+    /// Words wider than the box are put on a line of their own. Each word is
+    /// measured with the space before it and added to the width of the line,
+    /// so lines are not measured again for every word.
+    fn break_lines(&self, font: &dyn FontReference) -> Vec<Line> {
+        let size = self.size as f32;
+        let mut lines: Vec<Line> = Vec::new();
+        for word in words(&self.text) {
+            if let Some(line) = lines.last_mut().filter(|_| !word.hard_break) {
+                let width = line.width + font.text_width(&format!(" {}", word.text), size);
+                if width <= self.width {
+                    line.words.push(word.text.to_owned());
+                    line.width = width;
+                    continue;
+                }
+            }
+            lines.push(Line {
+                start: word.start,
+                hard_break: word.hard_break,
+                words: vec![word.text.to_owned()],
+                width: font.text_width(word.text, size),
+            });
+        }
+        lines
+    }
+}
+
+/// A paragraph broken into positioned lines.
+#[derive(Debug)]
+pub struct TextLayout {
+    font: String,
+    size: u32,
+    lines: Vec<PlacedLine>,
+    /// The text that did not fit in the box, if any.
+    pub overflow: Option<String>,
+}
+
+impl TextLayout {
+    /// The number of lines that fit in the box.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Create the operations to show the lines, in their own text object.
+    ///
+    /// Words in justified lines are shown separately, with a `TJ` of just the
+    /// adjustment between them as `Tw` only applies to single byte spaces, so
+    /// that they are still shaped. See section 9.3.3 of the PDF spec.
+    pub fn operations(&self, font: &dyn FontReference) -> Vec<Operation> {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec![self.font.as_str().into(), self.size.into()]),
+        ];
+        let mut position = (0., 0.);
+        for line in self.lines.iter() {
+            operations.push(Operation::new(
+                "Td",
+                vec![
+                    Object::Real(line.x - position.0),
+                    Object::Real(line.y - position.1),
+                ],
+            ));
+            position = (line.x, line.y);
+
+            if line.word_spacing == 0. {
+                operations.extend(font.render_operations(&line.words.join(" ")));
+            } else {
+                let adjustment = -line.word_spacing * 1000. / self.size as f32;
+                operations.extend(font.render_operations(&line.words[0]));
+                for word in line.words[1..].iter() {
+                    operations.push(Operation::new(
+                        "TJ",
+                        vec![Object::Array(vec![Object::Real(adjustment)])],
+                    ));
+                    operations.extend(font.render_operations(&format!(" {}", word)));
+                }
+            }
+        }
+        operations.push(Operation::new("ET", vec![]));
+        operations
+    }
+}
+
+#[derive(Debug)]
+struct PlacedLine {
+    words: Vec<String>,
+    x: f32,
+    y: f32,
+    /// Extra space added between each word to justify the line.
+    word_spacing: f32,
+}
+
+struct Line {
+    /// Byte offset of the first word in the paragraph text.
+    start: usize,
+    /// Whether the line starts after an explicit line break.
+    hard_break: bool,
+    words: Vec<String>,
+    width: f32,
+}
+
+struct Word<'a> {
+    start: usize,
+    text: &'a str,
+    hard_break: bool,
+}
+
+/// Split text into words, noting which words follow a line break.
+fn words(text: &str) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut hard_break = false;
+    for (i, c) in text.char_indices().chain(once((text.len(), ' '))) {
+        if c.is_whitespace() {
+            if let Some(start) = start.take() {
+                words.push(Word {
+                    start,
+                    text: &text[start..i],
+                    hard_break,
+                });
+                hard_break = false;
+            }
+            if c == '\n' {
+                hard_break = true;
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fonts::{type1, FontType0Builder};
+    use lopdf::Document;
+
+    fn courier() -> impl FontReference {
+        // every glyph in Courier is 600 units wide
+        type1("Courier").add_to_doc(&mut Document::with_version("1.5"))
+    }
+
+    fn lines(layout: &TextLayout) -> Vec<String> {
+        layout
+            .lines
+            .iter()
+            .map(|line| line.words.join(" "))
+            .collect()
+    }
+
+    #[test]
+    fn wraps_words_to_width() {
+        let font = courier();
+        // 10 characters per line at size 10
+        let layout = Paragraph::new("one two three four five\nsix", "F1", 10)
+            .width(60.)
+            .layout(&font);
+
+        assert_eq!(lines(&layout), vec!["one two", "three four", "five", "six"]);
+        assert!(layout.overflow.is_none());
+    }
+
+    #[test]
+    fn aligns_lines() {
+        let font = courier();
+        let paragraph = Paragraph::new("ab cd\nefg", "F1", 10).width(60.);
+
+        let layout = paragraph.clone().align(Align::Right).layout(&font);
+        assert_eq!(layout.lines[0].x, 30.);
+        let layout = paragraph.clone().align(Align::Centre).layout(&font);
+        assert_eq!(layout.lines[1].x, 21.);
+        let layout = Paragraph::new("ab cd ef gh", "F1", 10)
+            .width(60.)
+            .align(Align::Justify)
+            .layout(&font);
+        assert_eq!(lines(&layout), vec!["ab cd ef", "gh"]);
+        assert_eq!(layout.lines[0].word_spacing, 6.);
+        assert_eq!(layout.lines[1].word_spacing, 0.);
+    }
+
+    #[test]
+    fn justified_words_are_shaped() {
+        let font = FontType0Builder::from_file("assets/Georgia.ttf")
+            .unwrap()
+            .shaping(true)
+            .add_to_doc(&mut Document::with_version("1.5"));
+        let width = font.text_width("ab cd ef", 10.) + 6.;
+        let layout = Paragraph::new("ab cd ef gh", "F1", 10)
+            .width(width)
+            .align(Align::Justify)
+            .layout(&font);
+        let operators: Vec<_> = layout
+            .operations(&font)
+            .into_iter()
+            .map(|op| op.operator)
+            .collect();
+        // each word is its own ActualText span, with the spaces stretched
+        // between them
+        assert_eq!(operators.iter().filter(|op| *op == "BDC").count(), 4);
+        assert_eq!(
+            operators[2..12],
+            ["Td", "BDC", "TJ", "EMC", "TJ", "BDC", "TJ", "EMC", "TJ", "BDC"]
+        );
+    }
+
+    #[test]
+    fn reports_overflow() {
+        let font = courier();
+        let layout = Paragraph::new("one two three four five", "F1", 10)
+            .position(0., 100.)
+            .width(60.)
+            .height(25.)
+            .leading(12.)
+            .layout(&font);
+
+        assert_eq!(layout.line_count(), 2);
+        assert_eq!(layout.overflow.as_deref(), Some("five"));
+        assert_eq!(layout.lines[0].y, 100. - 6.29);
+    }
+}
@@ -1,4 +1,13 @@
+pub mod colour_space;
 pub mod commands;
 pub mod config;
+pub mod dedupe;
 pub mod document;
 pub mod fonts;
+pub mod form;
+pub mod graphics_state;
+pub mod import;
+pub mod layout;
+pub mod pattern;
+pub mod shading;
+pub mod shapes;
@@ -32,10 +32,14 @@ struct CreateArgs {
     #[arg(short, long)]
     pub compress_content: bool,
 
-    /// Use the subsetted font.
+    /// Subset embedded fonts to the glyphs that are used.
     #[arg(short, long)]
     pub subset: bool,
 
+    /// Merge identical fonts and images.
+    #[arg(short, long)]
+    pub dedupe: bool,
+
     #[command(subcommand)]
     pub command: CreateCommand,
 }
@@ -60,6 +64,7 @@ impl From<CreateArgs> for CreateConfig {
             compress: !args.no_compress,
             compress_content: args.compress_content,
             subset: args.subset,
+            dedupe: args.dedupe,
             output,
         }
     }
@@ -0,0 +1,165 @@
+//! Shading and tiling patterns
+//!
+//! A pattern is used as a colour, selected with `scn` or `SCN` in the
+//! `Pattern` colour space, and paints whatever is filled or stroked while it
+//! is set. Shading patterns paint a [Shading], and tiling patterns repeat a
+//! small cell drawn with [ContentBuilder], such as hatching.
+//!
+//! Pattern space is the default coordinate space of the page, so patterns do
+//! not move with changes to the transformation matrix. See section 8.7 of the
+//! PDF spec.
+use lopdf::{content::Content, dictionary, Document, Object, Stream};
+
+use crate::document::{ContentBuilder, Resources};
+use crate::shading::Shading;
+
+/// A colour that varies across the area being painted.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Pattern {
+    /// A shading pattern (type 2), see section 8.7.4 of the PDF spec.
+    Shading(Shading),
+    /// A tiling pattern (type 1), see section 8.7.3 of the PDF spec.
+    Tiling(TilingPattern),
+}
+
+impl Pattern {
+    /// Whether the pattern is painted in a colour given when it is selected.
+    pub fn is_uncoloured(&self) -> bool {
+        matches!(self, Self::Tiling(tiling) if !tiling.coloured)
+    }
+
+    /// Add the pattern to the document, returning the object to put in the
+    /// resources.
+    ///
+    /// Tiling patterns are streams, which must be indirect objects.
+    pub fn add_to_doc(&self, doc: &mut Document) -> Object {
+        match self {
+            Self::Shading(shading) => dictionary! {
+                "PatternType" => 2,
+                "Shading" => shading.as_dictionary(),
+            }
+            .into(),
+            Self::Tiling(tiling) => doc.add_object(tiling.as_stream()).into(),
+        }
+    }
+}
+
+/// A cell that is repeated to fill the area being painted.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TilingPattern {
+    size: (f32, f32),
+    step: (f32, f32),
+    coloured: bool,
+    content: Vec<u8>,
+}
+
+impl TilingPattern {
+    /// A pattern whose cell sets its own colours (paint type 1).
+    ///
+    /// The cell is drawn by a closure from `(0, 0)` to `(width, height)`, and
+    /// anything drawn outside of it is clipped. Cells can not use fonts or
+    /// images as they have no resources of their own.
+    pub fn coloured<F>(width: f32, height: f32, draw: F) -> Self
+    where
+        F: FnOnce(ContentBuilder) -> ContentBuilder,
+    {
+        Self::new(width, height, true, draw)
+    }
+
+    /// A pattern whose cell is only a shape, painted in a colour given when
+    /// the pattern is selected (paint type 2).
+    ///
+    /// The cell must not set any colours, see
+    /// [ContentBuilder::fill_uncoloured_pattern].
+    pub fn uncoloured<F>(width: f32, height: f32, draw: F) -> Self
+    where
+        F: FnOnce(ContentBuilder) -> ContentBuilder,
+    {
+        Self::new(width, height, false, draw)
+    }
+
+    fn new<F>(width: f32, height: f32, coloured: bool, draw: F) -> Self
+    where
+        F: FnOnce(ContentBuilder) -> ContentBuilder,
+    {
+        let resources = Resources::default();
+        let operations = draw(ContentBuilder::new(&resources)).build_operations();
+        let content = Content { operations }
+            .encode()
+            .expect("could not encode pattern cell");
+        Self {
+            size: (width, height),
+            step: (width, height),
+            coloured,
+            content,
+        }
+    }
+
+    /// Repeat the cell at a different spacing to its size (`XStep` and
+    /// `YStep`), leaving gaps or overlapping cells.
+    pub fn step(mut self, x: f32, y: f32) -> Self {
+        if x == 0. || y == 0. {
+            panic!("Tiling pattern steps can not be 0 but were {} and {}", x, y);
+        }
+        self.step = (x, y);
+        self
+    }
+
+    fn as_stream(&self) -> Stream {
+        let dict = dictionary! {
+            "Type" => "Pattern",
+            "PatternType" => 1,
+            "PaintType" => if self.coloured { 1 } else { 2 },
+            "TilingType" => 1,
+            "BBox" => vec![0.into(), 0.into(), self.size.0.into(), self.size.1.into()],
+            "XStep" => self.step.0,
+            "YStep" => self.step.1,
+            "Resources" => Resources::default().as_dictionary(),
+        };
+        Stream::new(dict, self.content.clone()).with_compression(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Colour;
+
+    #[test]
+    fn tiling_patterns_are_streams() {
+        let mut doc = Document::with_version("1.7");
+        let pattern = Pattern::Tiling(
+            TilingPattern::uncoloured(4., 4., |b| b.draw_line((0., 0.), (4., 4.)).stroke_path())
+                .step(4., 8.),
+        );
+        assert!(pattern.is_uncoloured());
+
+        let id = pattern.add_to_doc(&mut doc).as_reference().unwrap();
+        let stream = doc.get_object(id).unwrap().as_stream().unwrap();
+        let dict = &stream.dict;
+        assert_eq!(dict.get(b"PatternType").unwrap().as_i64().unwrap(), 1);
+        assert_eq!(dict.get(b"PaintType").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(dict.get(b"BBox").unwrap().as_array().unwrap().len(), 4);
+        assert_eq!(dict.get(b"YStep").unwrap().as_float().unwrap(), 8.);
+        assert_eq!(
+            String::from_utf8(stream.content.clone()).unwrap(),
+            "0 0 m\n4 4 l\nS"
+        );
+    }
+
+    #[test]
+    fn shading_patterns_are_inline() {
+        let mut doc = Document::with_version("1.7");
+        let shading = Shading::axial(
+            (0., 0.),
+            (1., 0.),
+            &[(0., Colour::Rgb(0., 0., 0.)), (1., Colour::Rgb(1., 1., 1.))],
+        );
+        let pattern = Pattern::Shading(shading);
+        assert!(!pattern.is_uncoloured());
+        let object = pattern.add_to_doc(&mut doc);
+        let dict = object.as_dict().unwrap();
+        assert_eq!(dict.get(b"PatternType").unwrap().as_i64().unwrap(), 2);
+        assert!(doc.objects.is_empty());
+    }
+}
@@ -0,0 +1,238 @@
+//! Gradients as shadings and shading patterns
+//!
+//! A shading paints a smooth blend of colours, either along a line (axial)
+//! or between two circles (radial). It can be painted directly with `sh`,
+//! which fills the current clipping region, or wrapped in a
+//! [crate::pattern::Pattern] and used as the colour of fills and strokes. See
+//! section 8.7.4.5 of the PDF spec.
+//!
+//! Colours blend between stops with a type 2 function for each pair of stops,
+//! joined together by a type 3 stitching function. See section 7.10 of the PDF
+//! spec.
+use lopdf::{dictionary, Dictionary, Object};
+
+use crate::document::Colour;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Geometry {
+    Axial {
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    Radial {
+        from: (f32, f32, f32),
+        to: (f32, f32, f32),
+    },
+}
+
+/// An axial or radial gradient in a device colour space.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shading {
+    geometry: Geometry,
+    stops: Vec<(f32, Colour)>,
+    extend: (bool, bool),
+}
+
+impl Shading {
+    /// Blend colours along the line from `from` to `to` (type 2).
+    ///
+    /// Stops are positions along the line from 0 to 1, in increasing order,
+    /// with the colour at that position. The first and last colours continue
+    /// to the ends of the line.
+    pub fn axial(from: (f32, f32), to: (f32, f32), stops: &[(f32, Colour)]) -> Self {
+        Self::new(Geometry::Axial { from, to }, stops)
+    }
+
+    /// Blend colours between a starting and ending circle (type 3).
+    ///
+    /// Circles are given as `(x, y, radius)`, and stops are as for
+    /// [Shading::axial].
+    pub fn radial(from: (f32, f32, f32), to: (f32, f32, f32), stops: &[(f32, Colour)]) -> Self {
+        Self::new(Geometry::Radial { from, to }, stops)
+    }
+
+    fn new(geometry: Geometry, stops: &[(f32, Colour)]) -> Self {
+        if stops.len() < 2 {
+            panic!(
+                "A gradient needs at least two stops but had {}",
+                stops.len()
+            );
+        }
+        if stops.windows(2).any(|pair| pair[0].0 > pair[1].0)
+            || stops.iter().any(|&(t, _)| !(0. ..=1.).contains(&t))
+        {
+            panic!(
+                "Gradient stops must increase from 0 to 1 but were {:?}",
+                stops
+            );
+        }
+        let space = stops[0].1.device_space();
+        if space.is_none() || stops.iter().any(|(_, c)| c.device_space() != space) {
+            panic!(
+                "Gradient stops must all be in the same device colour space but were {:?}",
+                stops
+            );
+        }
+        let mut stops = stops.to_vec();
+        if stops[0].0 > 0. {
+            stops.insert(0, (0., stops[0].1));
+        }
+        if stops[stops.len() - 1].0 < 1. {
+            stops.push((1., stops[stops.len() - 1].1));
+        }
+        Self {
+            geometry,
+            stops,
+            extend: (false, false),
+        }
+    }
+
+    /// Keep painting the end colours beyond the start and end of the gradient.
+    pub fn extend(mut self, start: bool, end: bool) -> Self {
+        self.extend = (start, end);
+        self
+    }
+
+    pub fn as_dictionary(&self) -> Dictionary {
+        let (shading_type, coords) = match self.geometry {
+            Geometry::Axial { from, to } => (2, vec![from.0, from.1, to.0, to.1]),
+            Geometry::Radial { from, to } => (3, vec![from.0, from.1, from.2, to.0, to.1, to.2]),
+        };
+        dictionary! {
+            "ShadingType" => shading_type,
+            "ColorSpace" => self.stops[0].1.device_space().unwrap(),
+            "Coords" => coords.into_iter().map(Object::Real).collect::<Vec<_>>(),
+            "Function" => self.function(),
+            "Extend" => vec![self.extend.0.into(), self.extend.1.into()],
+        }
+    }
+
+    /// Interpolate between stops, stitching several functions together if
+    /// there are more than two.
+    fn function(&self) -> Dictionary {
+        let functions: Vec<Dictionary> = self
+            .stops
+            .windows(2)
+            .map(|pair| interpolate(pair[0].1, pair[1].1))
+            .collect();
+        if functions.len() == 1 {
+            return functions.into_iter().next().unwrap();
+        }
+
+        let bounds = self.stops[1..self.stops.len() - 1]
+            .iter()
+            .map(|&(t, _)| t.into())
+            .collect::<Vec<Object>>();
+        let encode = functions
+            .iter()
+            .flat_map(|_| [0.into(), 1.into()])
+            .collect::<Vec<Object>>();
+        dictionary! {
+            "FunctionType" => 3,
+            "Domain" => vec![0.into(), 1.into()],
+            "Functions" => functions.into_iter().map(Object::Dictionary).collect::<Vec<_>>(),
+            "Bounds" => bounds,
+            "Encode" => encode,
+        }
+    }
+}
+
+/// A linear blend between two colours over the domain 0 to 1.
+fn interpolate(from: Colour, to: Colour) -> Dictionary {
+    let colour = |c: Colour| {
+        c.components()
+            .into_iter()
+            .map(Object::Real)
+            .collect::<Vec<_>>()
+    };
+    dictionary! {
+        "FunctionType" => 2,
+        "Domain" => vec![0.into(), 1.into()],
+        "C0" => colour(from),
+        "C1" => colour(to),
+        "N" => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Colour = Colour::Rgb(1., 0., 0.);
+    const GREEN: Colour = Colour::Rgb(0., 1., 0.);
+    const BLUE: Colour = Colour::Rgb(0., 0., 1.);
+
+    #[test]
+    fn two_stops_use_one_function() {
+        let dict = Shading::axial((0., 0.), (100., 0.), &[(0., RED), (1., BLUE)])
+            .extend(true, false)
+            .as_dictionary();
+        assert_eq!(dict.get(b"ShadingType").unwrap().as_i64().unwrap(), 2);
+        assert_eq!(dict.get(b"Coords").unwrap().as_array().unwrap().len(), 4);
+        let function = dict.get(b"Function").unwrap().as_dict().unwrap();
+        assert_eq!(function.get(b"FunctionType").unwrap().as_i64().unwrap(), 2);
+        let extend = dict.get(b"Extend").unwrap().as_array().unwrap();
+        assert!(extend[0].as_bool().unwrap() && !extend[1].as_bool().unwrap());
+    }
+
+    #[test]
+    fn more_stops_are_stitched() {
+        let dict = Shading::radial(
+            (50., 50., 0.),
+            (50., 50., 40.),
+            &[(0.2, RED), (0.5, GREEN), (0.6, BLUE), (1., RED)],
+        )
+        .as_dictionary();
+        assert_eq!(dict.get(b"ShadingType").unwrap().as_i64().unwrap(), 3);
+        let floats = |key: &[u8], dict: &Dictionary| -> Vec<f32> {
+            dict.get(key)
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|o| o.as_float().unwrap())
+                .collect()
+        };
+        let function = dict.get(b"Function").unwrap().as_dict().unwrap();
+        assert_eq!(function.get(b"FunctionType").unwrap().as_i64().unwrap(), 3);
+        // the first colour is held from 0 up to the first stop
+        let functions = function.get(b"Functions").unwrap().as_array().unwrap();
+        assert_eq!(functions.len(), 4);
+        let first = functions[0].as_dict().unwrap();
+        assert_eq!(floats(b"C0", first), floats(b"C1", first));
+        assert_eq!(floats(b"Bounds", function), vec![0.2, 0.5, 0.6]);
+        assert_eq!(floats(b"Domain", function), vec![0., 1.]);
+        assert_eq!(floats(b"Encode", function).len(), 8);
+    }
+
+    #[test]
+    fn colour_space_follows_the_stops() {
+        let dict = Shading::axial(
+            (0., 0.),
+            (1., 0.),
+            &[
+                (0., Colour::Cmyk(1., 0., 0., 0.)),
+                (1., Colour::Cmyk(0., 0., 0., 1.)),
+            ],
+        )
+        .as_dictionary();
+        assert_eq!(
+            dict.get(b"ColorSpace").unwrap().as_name_str().unwrap(),
+            "DeviceCMYK"
+        );
+        let function = dict.get(b"Function").unwrap().as_dict().unwrap();
+        assert_eq!(function.get(b"C0").unwrap().as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "same device colour space")]
+    fn stops_in_different_colour_spaces() {
+        Shading::axial((0., 0.), (1., 1.), &[(0., RED), (1., Colour::Gray(0.))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must increase")]
+    fn stops_out_of_order() {
+        Shading::axial((0., 0.), (1., 1.), &[(0.5, RED), (0.2, BLUE)]);
+    }
+}
@@ -0,0 +1,408 @@
+//! Shapes drawn with [ContentBuilder]
+//!
+//! Each shape is a path with an optional stroke and fill. Drawing a shape
+//! saves and restores the graphics state around it, so its colours and line
+//! width do not leak into the content that follows. Shapes that are neither
+//! stroked nor filled are stroked with the current stroke colour and width.
+//!
+//! Angles are in radians, anticlockwise from the positive x axis.
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use crate::document::{Colour, ContentBuilder};
+
+#[derive(Clone, Debug)]
+enum Path {
+    Rectangle {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    RoundedRectangle {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+    },
+    Ellipse {
+        x: f32,
+        y: f32,
+        radius_x: f32,
+        radius_y: f32,
+    },
+    Arc {
+        x: f32,
+        y: f32,
+        radius: f32,
+        start: f32,
+        sweep: f32,
+    },
+    Polygon {
+        x: f32,
+        y: f32,
+        radius: f32,
+        sides: u32,
+    },
+    Arrow {
+        from: (f32, f32),
+        to: (f32, f32),
+        head: f32,
+    },
+}
+
+/// A path with its stroke and fill.
+#[derive(Clone, Debug)]
+pub(crate) struct Shape {
+    path: Path,
+    stroke: Option<(Colour, f32)>,
+    fill: Option<Colour>,
+}
+
+/// A rectangle with its bottom left corner at `(x, y)`, drawn with `re`.
+pub(crate) fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Shape {
+    Shape::new(Path::Rectangle {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// A rectangle with quarter circle corners, see
+/// [ContentBuilder::append_rounded_rectangle].
+pub(crate) fn rounded_rectangle(x: f32, y: f32, width: f32, height: f32, radius: f32) -> Shape {
+    Shape::new(Path::RoundedRectangle {
+        x,
+        y,
+        width,
+        height,
+        radius,
+    })
+}
+
+/// An ellipse centred on `(x, y)`.
+pub(crate) fn ellipse(x: f32, y: f32, radius_x: f32, radius_y: f32) -> Shape {
+    Shape::new(Path::Ellipse {
+        x,
+        y,
+        radius_x,
+        radius_y,
+    })
+}
+
+/// A circle centred on `(x, y)`.
+pub(crate) fn circle(x: f32, y: f32, radius: f32) -> Shape {
+    ellipse(x, y, radius, radius)
+}
+
+/// An open arc of a circle centred on `(x, y)`.
+///
+/// The arc starts at the angle `start` and turns through `sweep`, clockwise if
+/// it is negative. It is split into Bézier curves of at most a quarter turn.
+pub(crate) fn arc(x: f32, y: f32, radius: f32, start: f32, sweep: f32) -> Shape {
+    Shape::new(Path::Arc {
+        x,
+        y,
+        radius,
+        start,
+        sweep,
+    })
+}
+
+/// A regular polygon centred on `(x, y)` with a vertex straight up.
+///
+/// The radius is the distance from the centre to each vertex.
+pub(crate) fn polygon(x: f32, y: f32, radius: f32, sides: u32) -> Shape {
+    if sides < 3 {
+        panic!("A polygon needs at least 3 sides, not {}", sides);
+    }
+    Shape::new(Path::Polygon {
+        x,
+        y,
+        radius,
+        sides,
+    })
+}
+
+/// A straight arrow with a triangular head at `to`.
+///
+/// The head is `head` long and as wide. It is filled with the fill colour,
+/// or the stroke colour if the arrow is not filled, and the shaft stops at
+/// the head so that wide lines do not poke through the point.
+pub(crate) fn arrow(from: (f32, f32), to: (f32, f32), head: f32) -> Shape {
+    Shape::new(Path::Arrow { from, to, head })
+}
+
+impl Shape {
+    fn new(path: Path) -> Self {
+        Self {
+            path,
+            stroke: None,
+            fill: None,
+        }
+    }
+
+    /// Stroke the outline with a colour and line width.
+    pub(crate) fn stroke(mut self, colour: Colour, width: f32) -> Self {
+        self.stroke = Some((colour, width));
+        self
+    }
+
+    /// Fill the inside with a colour.
+    pub(crate) fn fill(mut self, colour: Colour) -> Self {
+        self.fill = Some(colour);
+        self
+    }
+
+    /// Append the path without painting it, to paint or clip to it.
+    pub(crate) fn append_path<'a>(&self, b: ContentBuilder<'a>) -> ContentBuilder<'a> {
+        match self.path {
+            Path::Rectangle {
+                x,
+                y,
+                width,
+                height,
+            } => b.append_rectangle(x, y, width, height),
+            Path::RoundedRectangle {
+                x,
+                y,
+                width,
+                height,
+                radius,
+            } => b.append_rounded_rectangle(x, y, width, height, radius),
+            Path::Ellipse {
+                x,
+                y,
+                radius_x,
+                radius_y,
+            } => b.append_ellipse(x, y, radius_x, radius_y),
+            Path::Arc {
+                x,
+                y,
+                radius,
+                start,
+                sweep,
+            } => append_arc(b, (x, y), radius, start, sweep),
+            Path::Polygon {
+                x,
+                y,
+                radius,
+                sides,
+            } => {
+                let vertex = |i: u32| {
+                    let angle = FRAC_PI_2 + TAU * i as f32 / sides as f32;
+                    (x + radius * angle.cos(), y + radius * angle.sin())
+                };
+                let (x0, y0) = vertex(0);
+                (1..sides)
+                    .map(vertex)
+                    .fold(b.begin_path(x0, y0), |b, (x, y)| {
+                        b.append_straight_line(x, y)
+                    })
+                    .close_subpath()
+            }
+            Path::Arrow { from, to, head } => {
+                let [base, tip, left, right] = arrow_points(from, to, head);
+                b.draw_line(from, base)
+                    .begin_path(tip.0, tip.1)
+                    .append_straight_line(left.0, left.1)
+                    .append_straight_line(right.0, right.1)
+                    .close_subpath()
+            }
+        }
+    }
+
+    /// Draw the shape with its stroke and fill.
+    pub(crate) fn draw<'a>(&self, b: ContentBuilder<'a>) -> ContentBuilder<'a> {
+        let mut b = b.save_graphics_state();
+        if let Some((colour, width)) = self.stroke {
+            b = b.scolour(colour).line_width(width);
+        }
+        if let Some(colour) = self.fill {
+            b = b.colour(colour);
+        }
+
+        if let Path::Arrow { from, to, head } = self.path {
+            if let (None, Some((colour, _))) = (self.fill, self.stroke) {
+                b = b.colour(colour);
+            }
+            let [base, tip, left, right] = arrow_points(from, to, head);
+            return b
+                .draw_line(from, base)
+                .stroke_path()
+                .begin_path(tip.0, tip.1)
+                .append_straight_line(left.0, left.1)
+                .append_straight_line(right.0, right.1)
+                .close_subpath()
+                .fill_path()
+                .restore_graphics_state();
+        }
+
+        let b = self.append_path(b);
+        match (self.stroke, self.fill) {
+            (Some(_), Some(_)) => b.fill_stroke_path(),
+            (None, Some(_)) => b.fill_path(),
+            (_, None) => b.stroke_path(),
+        }
+        .restore_graphics_state()
+    }
+}
+
+/// Append an arc as Bézier curves of at most a quarter turn each.
+///
+/// The control points of a curve through an angle θ are `4/3 tan(θ/4)` times
+/// the radius along the tangents at its ends.
+fn append_arc(
+    b: ContentBuilder,
+    centre: (f32, f32),
+    radius: f32,
+    start: f32,
+    sweep: f32,
+) -> ContentBuilder {
+    let point = |angle: f32| {
+        (
+            centre.0 + radius * angle.cos(),
+            centre.1 + radius * angle.sin(),
+        )
+    };
+    let segments = (sweep.abs() / FRAC_PI_2).ceil().max(1.) as u32;
+    let step = sweep / segments as f32;
+    let k = 4. / 3. * (step / 4.).tan() * radius;
+
+    let (x0, y0) = point(start);
+    (0..segments).fold(b.begin_path(x0, y0), |b, i| {
+        let (a0, a1) = (start + step * i as f32, start + step * (i + 1) as f32);
+        let (p0, p3) = (point(a0), point(a1));
+        b.append_curve(
+            p0.0 - k * a0.sin(),
+            p0.1 + k * a0.cos(),
+            p3.0 + k * a1.sin(),
+            p3.1 - k * a1.cos(),
+            p3.0,
+            p3.1,
+        )
+    })
+}
+
+/// The end of the shaft, the tip, and the two back corners of an arrow head.
+fn arrow_points(from: (f32, f32), to: (f32, f32), head: f32) -> [(f32, f32); 4] {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    let (ux, uy) = (dx / length, dy / length);
+    let base = (to.0 - ux * head, to.1 - uy * head);
+    let half = head / 2.;
+    [
+        base,
+        to,
+        (base.0 - uy * half, base.1 + ux * half),
+        (base.0 + uy * half, base.1 - ux * half),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::*;
+    use crate::document::Resources;
+
+    fn operators(b: ContentBuilder) -> String {
+        b.build_operations()
+            .into_iter()
+            .map(|op| op.operator)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn points(b: ContentBuilder) -> Vec<(f32, f32)> {
+        b.build_operations()
+            .iter()
+            .filter_map(|op| match op.operands.as_slice() {
+                [.., x, y] => Some((
+                    (x.as_float().unwrap() * 1000.).round() / 1000.,
+                    (y.as_float().unwrap() * 1000.).round() / 1000.,
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn styles_choose_the_painting_operator() {
+        let resources = Resources::default();
+        let b = || ContentBuilder::new(&resources);
+        let square = rectangle(0., 0., 10., 10.);
+
+        assert_eq!(operators(square.draw(b())), "q re S Q");
+        assert_eq!(
+            operators(square.clone().fill(Colour::Rgb(1., 0., 0.)).draw(b())),
+            "q rg re f Q"
+        );
+        assert_eq!(
+            operators(
+                square
+                    .stroke(Colour::Rgb(0., 0., 0.), 2.)
+                    .fill(Colour::Rgb(1., 0., 0.))
+                    .draw(b())
+            ),
+            "q RG w rg re B Q"
+        );
+    }
+
+    #[test]
+    fn arcs_are_split_into_quarter_turns() {
+        let resources = Resources::default();
+        let b = ContentBuilder::new(&resources);
+        let b = arc(0., 0., 10., 0., -1.5 * PI).append_path(b);
+        let ends = points(b);
+        // clockwise from the right through the bottom and left to the top
+        assert_eq!(ends, vec![(10., 0.), (0., -10.), (-10., 0.), (0., 10.)]);
+
+        let b = ContentBuilder::new(&resources);
+        let b = arc(0., 0., 10., 0., FRAC_PI_2).append_path(b);
+        let operations = b.build_operations();
+        // the standard quarter circle control point distance
+        let control = operations[1].operands[1].as_float().unwrap();
+        assert!((control - 5.523).abs() < 0.001);
+    }
+
+    #[test]
+    fn polygons_start_at_the_top() {
+        let resources = Resources::default();
+        let b = polygon(0., 0., 10., 4).append_path(ContentBuilder::new(&resources));
+        assert_eq!(
+            points(b),
+            vec![(0., 10.), (-10., 0.), (0., -10.), (10., 0.)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "at least 3 sides")]
+    fn polygons_need_three_sides() {
+        polygon(0., 0., 10., 2);
+    }
+
+    #[test]
+    fn arrow_heads_are_filled_in_the_stroke_colour() {
+        let resources = Resources::default();
+        let b = arrow((0., 0.), (20., 0.), 4.)
+            .stroke(Colour::Rgb(0., 0., 1.), 1.)
+            .draw(ContentBuilder::new(&resources));
+        let operations = b.build_operations();
+        let fill = operations.iter().find(|op| op.operator == "rg").unwrap();
+        let blue: Vec<f32> = fill
+            .operands
+            .iter()
+            .map(|o| o.as_float().unwrap())
+            .collect();
+        assert_eq!(blue, vec![0., 0., 1.]);
+
+        let b = arrow((0., 0.), (20., 0.), 4.).append_path(ContentBuilder::new(&resources));
+        // the shaft stops at the back of the head
+        assert_eq!(
+            points(b),
+            vec![(0., 0.), (16., 0.), (20., 0.), (16., 2.), (16., -2.)]
+        );
+    }
+}